@@ -1,6 +1,7 @@
 use crate::commands::{self};
+use crate::config::Level;
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(name = "nub")]
@@ -15,28 +16,327 @@ enum Commands {
     Init,
     Add {
         files: Vec<String>,
+        #[arg(short, long)]
+        force: bool,
+    },
+    Rm {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long)]
+        cached: bool,
+        #[arg(short, long)]
+        force: bool,
+    },
+    Mv {
+        source: String,
+        destination: String,
+        #[arg(short, long)]
+        force: bool,
     },
     Commit {
         #[arg(short, long)]
         message: String,
     },
     Status,
-    Reset,
-    // Log,
-    // Diff { #[arg(long)] staged: bool },
-    // Checkout { target: String },
-    // Branch { name: Option<String> },
-    // Merge { branch: String },
+    #[command(group(ArgGroup::new("mode").args(["soft", "mixed", "hard"])))]
+    Reset {
+        #[arg(long, conflicts_with = "paths")]
+        soft: bool,
+        #[arg(long)]
+        mixed: bool,
+        #[arg(long, conflicts_with = "paths")]
+        hard: bool,
+        targets: Vec<String>,
+        #[arg(last = true)]
+        paths: Vec<String>,
+    },
+    Restore {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(short, long)]
+        source: Option<String>,
+        #[arg(short = 'S', long)]
+        staged: bool,
+    },
+    Log {
+        revision: Option<String>,
+        #[arg(short = 'n', long = "max-count")]
+        count: Option<usize>,
+        #[arg(long)]
+        oneline: bool,
+    },
+    Show {
+        revision: Option<String>,
+        #[arg(long)]
+        stat: bool,
+        #[arg(long, conflicts_with = "stat")]
+        name_only: bool,
+    },
+    Diff {
+        commit: Option<String>,
+        #[arg(long)]
+        staged: bool,
+    },
+    Checkout {
+        target: String,
+        #[arg(short, long)]
+        force: bool,
+    },
+    Branch {
+        #[arg(required_if_eq_any([("rename", "true"), ("delete", "true"), ("force_delete", "true")]))]
+        name: Option<String>,
+        start: Option<String>,
+        #[arg(short = 'm', long = "move")]
+        rename: bool,
+        #[arg(short, long, conflicts_with = "rename")]
+        delete: bool,
+        #[arg(short = 'D', conflicts_with_all = ["rename", "delete"])]
+        force_delete: bool,
+    },
+    Merge {
+        branch: String,
+    },
+    Tag {
+        name: Option<String>,
+        target: Option<String>,
+        #[arg(short, long, requires = "message")]
+        annotate: bool,
+        #[arg(short, long)]
+        message: Option<String>,
+        #[arg(short, long, conflicts_with_all = ["annotate", "message", "target"])]
+        list: bool,
+        #[arg(short, long, conflicts_with_all = ["annotate", "message", "list", "target"])]
+        delete: bool,
+        #[arg(short, long)]
+        force: bool,
+    },
+    CheckIgnore {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    #[command(group(ArgGroup::new("mode").required(true).args(["show_type", "show_size", "pretty"])))]
+    CatFile {
+        #[arg(short = 't')]
+        show_type: bool,
+        #[arg(short = 's')]
+        show_size: bool,
+        #[arg(short = 'p')]
+        pretty: bool,
+        object: String,
+    },
+    Migrate,
+    Gc,
+    Fsck,
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+        #[arg(long, global = true, group = "level")]
+        system: bool,
+        #[arg(long, global = true, group = "level")]
+        global: bool,
+        #[arg(long, global = true, group = "level")]
+        local: bool,
+        #[arg(long, global = true)]
+        show_origin: bool,
+    },
+    Prune {
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        #[arg(long)]
+        expire: Option<String>,
+    },
+    Stash {
+        #[command(subcommand)]
+        action: Option<StashAction>,
+    },
+    #[command(args_conflicts_with_subcommands = true)]
+    Reflog {
+        #[command(subcommand)]
+        action: Option<ReflogAction>,
+        refname: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    Get { key: String },
+    Set { key: String, value: String },
+    Unset { key: String },
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ReflogAction {
+    Show {
+        refname: Option<String>,
+    },
+    Expire {
+        #[arg(long)]
+        expire: Option<String>,
+        #[arg(long, conflicts_with = "refname")]
+        all: bool,
+        refname: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StashAction {
+    Push {
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    List,
+    Show {
+        stash: Option<String>,
+        #[arg(short, long)]
+        patch: bool,
+    },
+    Apply {
+        stash: Option<String>,
+    },
+    Pop {
+        stash: Option<String>,
+    },
+    Drop {
+        stash: Option<String>,
+    },
 }
 
 impl Cli {
     pub fn execute(self) -> Result<()> {
         match self.command {
             Commands::Init => commands::init::execute(),
-            Commands::Add { files } => commands::add::execute(files),
+            Commands::Add { files, force } => commands::add::execute(files, force),
+            Commands::Rm {
+                paths,
+                cached,
+                force,
+            } => commands::rm::execute(paths, cached, force),
+            Commands::Mv {
+                source,
+                destination,
+                force,
+            } => commands::mv::execute(source, destination, force),
             Commands::Commit { message } => commands::commit::execute(message),
             Commands::Status => commands::status::execute(),
-            Commands::Reset => commands::reset::execute(),
+            Commands::Reset {
+                soft,
+                mixed,
+                hard,
+                targets,
+                paths,
+            } => {
+                let mode: Option<commands::reset::Mode> = match (soft, mixed, hard) {
+                    (true, _, _) => Some(commands::reset::Mode::Soft),
+                    (_, true, _) => Some(commands::reset::Mode::Mixed),
+                    (_, _, true) => Some(commands::reset::Mode::Hard),
+                    _ => None,
+                };
+                commands::reset::execute(mode, targets, paths)
+            }
+            Commands::Restore {
+                paths,
+                source,
+                staged,
+            } => commands::restore::execute(paths, source, staged),
+            Commands::Log {
+                revision,
+                count,
+                oneline,
+            } => commands::log::execute(revision, count, oneline),
+            Commands::Show {
+                revision,
+                stat,
+                name_only,
+            } => {
+                let format: commands::show::Format = match (stat, name_only) {
+                    (true, _) => commands::show::Format::Stat,
+                    (_, true) => commands::show::Format::NameOnly,
+                    _ => commands::show::Format::Patch,
+                };
+                commands::show::execute(revision, format)
+            }
+            Commands::Diff { commit, staged } => commands::diff::execute(commit, staged),
+            Commands::Checkout { target, force } => commands::checkout::execute(target, force),
+            Commands::Branch {
+                name,
+                start,
+                rename,
+                delete,
+                force_delete,
+            } => commands::branch::execute(name, start, rename, delete, force_delete),
+            Commands::Merge { branch } => commands::merge::execute(branch),
+            Commands::Tag {
+                name,
+                target,
+                annotate: _,
+                message,
+                list,
+                delete,
+                force,
+            } => commands::tag::execute(name, target, message, list, delete, force),
+            Commands::CheckIgnore { paths } => commands::check_ignore::execute(paths),
+            Commands::CatFile {
+                show_type,
+                show_size,
+                pretty,
+                object,
+            } => commands::cat_file::execute(object, show_type, show_size, pretty),
+            Commands::Migrate => commands::migrate::execute(),
+            Commands::Gc => commands::gc::execute(),
+            Commands::Fsck => commands::fsck::execute(),
+            Commands::Config {
+                action,
+                system,
+                global,
+                local,
+                show_origin,
+            } => {
+                let level: Option<Level> = match (system, global, local) {
+                    (true, _, _) => Some(Level::System),
+                    (_, true, _) => Some(Level::Global),
+                    (_, _, true) => Some(Level::Local),
+                    _ => None,
+                };
+                let action: commands::config::Action = match action {
+                    ConfigAction::Get { key } => commands::config::Action::Get(key),
+                    ConfigAction::Set { key, value } => commands::config::Action::Set(key, value),
+                    ConfigAction::Unset { key } => commands::config::Action::Unset(key),
+                    ConfigAction::List => commands::config::Action::List,
+                };
+                commands::config::execute(action, level, show_origin)
+            }
+            Commands::Prune { dry_run, expire } => commands::prune::execute(dry_run, expire),
+            Commands::Stash { action } => {
+                let action: commands::stash::Action = match action {
+                    None => commands::stash::Action::Push(None),
+                    Some(StashAction::Push { message }) => commands::stash::Action::Push(message),
+                    Some(StashAction::List) => commands::stash::Action::List,
+                    Some(StashAction::Show { stash, patch }) => {
+                        commands::stash::Action::Show(stash, patch)
+                    }
+                    Some(StashAction::Apply { stash }) => commands::stash::Action::Apply(stash),
+                    Some(StashAction::Pop { stash }) => commands::stash::Action::Pop(stash),
+                    Some(StashAction::Drop { stash }) => commands::stash::Action::Drop(stash),
+                };
+                commands::stash::execute(action)
+            }
+            Commands::Reflog { action, refname } => {
+                let action: commands::reflog::Action = match action {
+                    None => commands::reflog::Action::Show(refname),
+                    Some(ReflogAction::Show { refname }) => commands::reflog::Action::Show(refname),
+                    Some(ReflogAction::Expire {
+                        expire,
+                        all,
+                        refname,
+                    }) => commands::reflog::Action::Expire {
+                        expire,
+                        all,
+                        refname,
+                    },
+                };
+                commands::reflog::execute(action)
+            }
         }
     }
 }
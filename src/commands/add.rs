@@ -1,15 +1,22 @@
 use crate::error::NubError;
-use crate::objects::Blob;
+use crate::ignore::IgnoreRules;
+use crate::index::Index;
+use crate::lockfile::LockFile;
 use crate::repository::Repository;
+use crate::worktree;
 use anyhow::Result;
 use colored::Colorize;
-use serde_json::Value;
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs::{self, DirEntry};
 use std::path::{Path, PathBuf};
 
-fn collect_files(path: &Path, repo_root: &Path, collected: &mut Vec<PathBuf>) -> Result<()> {
+fn collect_files(
+    path: &Path,
+    repo_root: &Path,
+    mut ignore: Option<&mut IgnoreRules>,
+    collected: &mut Vec<PathBuf>,
+) -> Result<()> {
     if path
         .file_name()
         .map(|n: &OsStr| n == ".nub-vcs")
@@ -23,89 +30,48 @@ fn collect_files(path: &Path, repo_root: &Path, collected: &mut Vec<PathBuf>) ->
     } else {
         repo_root.join(path)
     };
+    let rel_path: PathBuf = abs_path
+        .strip_prefix(repo_root)
+        .unwrap_or(&abs_path)
+        .to_path_buf();
 
     if abs_path.is_file() {
-        let rel_path: PathBuf = abs_path
-            .strip_prefix(repo_root)
-            .unwrap_or(&abs_path)
-            .to_path_buf();
         collected.push(rel_path);
     } else if abs_path.is_dir() {
+        let rel_dir: String = rel_path.to_string_lossy().replace("\\", "/");
+        if let Some(ignore) = ignore.as_deref_mut() {
+            ignore.load_dir(&rel_dir);
+        }
+
         for entry in fs::read_dir(&abs_path)? {
             let entry: DirEntry = entry?;
-            collect_files(&entry.path(), repo_root, collected)?;
+            if let Some(ignore) = ignore.as_deref() {
+                let name: String = entry.file_name().to_string_lossy().to_string();
+                let rel_entry: String = if rel_dir.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", rel_dir, name)
+                };
+                if ignore.is_ignored(&rel_entry, entry.path().is_dir()) {
+                    continue;
+                }
+            }
+            collect_files(&entry.path(), repo_root, ignore.as_deref_mut(), collected)?;
         }
     }
     Ok(())
 }
 
-pub fn execute(files: Vec<String>) -> Result<()> {
+pub fn execute(files: Vec<String>, force: bool) -> Result<()> {
     let repo = Repository::find()?;
-    let index_path = repo.index_path();
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
 
-    let index: Vec<Value> = if index_path.exists() {
-        let data = fs::read_to_string(&index_path)?;
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    let committed_tree: HashMap<String, String> = repo.head_files()?;
+    let mut tracked: HashMap<String, String> = committed_tree.clone();
+    index.apply_to(&mut tracked);
 
-    let mut index_map: HashMap<String, String> = index
-        .iter()
-        .filter_map(|v| {
-            Some((
-                v.get("path")?.as_str()?.replace("\\", "/"),
-                v.get("hash")?.as_str()?.to_string(),
-            ))
-        })
-        .collect();
-
-    let head_path = repo.head_path();
-    let committed_tree: HashMap<String, String> = if head_path.exists() {
-        let head_ref = fs::read_to_string(&head_path)?;
-        let head_ref_path = head_ref.trim_start_matches("ref: ").trim();
-        let branch_path = repo.nub_dir.join(head_ref_path);
-
-        if branch_path.exists() {
-            let commit_hash = fs::read_to_string(&branch_path)?.trim().to_string();
-            let commit_path = repo.commits_dir().join(&commit_hash);
-
-            if commit_path.exists() {
-                let commit_data = fs::read_to_string(commit_path)?;
-                let commit_json: Value = serde_json::from_str(&commit_data)?;
-
-                if let Some(tree_hash) = commit_json.get("tree").and_then(|v| v.as_str()) {
-                    let tree_path = repo.objects_dir().join(tree_hash);
-                    if tree_path.exists() {
-                        let tree_data = fs::read_to_string(tree_path)?;
-                        let tree_json: Value = serde_json::from_str(&tree_data)?;
-
-                        tree_json["entries"]
-                            .as_array()
-                            .unwrap_or(&vec![])
-                            .iter()
-                            .filter_map(|e| {
-                                Some((
-                                    e.get("name")?.as_str()?.replace("\\", "/"),
-                                    e.get("hash")?.as_str()?.to_string(),
-                                ))
-                            })
-                            .collect()
-                    } else {
-                        HashMap::new()
-                    }
-                } else {
-                    HashMap::new()
-                }
-            } else {
-                HashMap::new()
-            }
-        } else {
-            HashMap::new()
-        }
-    } else {
-        HashMap::new()
-    };
+    let mut ignore: IgnoreRules = repo.ignore_rules();
 
     let mut all_files: Vec<PathBuf> = Vec::new();
     for f in files {
@@ -113,37 +79,78 @@ pub fn execute(files: Vec<String>) -> Result<()> {
         if !path.exists() {
             return Err(NubError::FileNotFound(f.clone()).into());
         }
-        collect_files(&path, &repo.root, &mut all_files)?;
+        let spec: String = worktree::repo_relative(&repo, &f)?;
+
+        let mut has_tracked: bool = false;
+        for tracked_path in tracked.keys() {
+            if worktree::matches_pathspec(tracked_path, &spec) {
+                has_tracked = true;
+                if repo.root.join(tracked_path).is_file() {
+                    all_files.push(PathBuf::from(tracked_path));
+                }
+            }
+        }
+
+        if force {
+            collect_files(&repo.root.join(&spec), &repo.root, None, &mut all_files)?;
+            continue;
+        }
+
+        if !spec.is_empty()
+            && let Some(rule) = ignore.check(&spec, path.is_dir())
+            && !rule.negated
+        {
+            if !has_tracked {
+                println!(
+                    "{} {} is ignored by {}:{} ({}); use --force to add it",
+                    "✗".red().bold(),
+                    spec.cyan(),
+                    rule.source,
+                    rule.line,
+                    rule.text
+                );
+            }
+            continue;
+        }
+
+        collect_files(
+            &repo.root.join(&spec),
+            &repo.root,
+            Some(&mut ignore),
+            &mut all_files,
+        )?;
     }
     all_files.sort();
     all_files.dedup();
 
+    let merging = repo.merge_head_path().exists();
     let mut changed_count = 0;
+    let cached_index: Index = index.clone();
 
     for file_path in all_files {
         let full_path = repo.root.join(&file_path);
-        let content = fs::read(&full_path).map_err(NubError::IoError)?;
-        let blob = Blob::new(content);
         let relative_path = file_path.to_string_lossy().replace("\\", "/");
+        let Some(hash) = worktree::hash_file_cached(&repo, &mut index, &relative_path) else {
+            return Err(NubError::FileNotFound(relative_path).into());
+        };
 
-        let is_changed = match index_map.get(&relative_path) {
-            Some(existing_hash) => existing_hash != &blob.hash,
+        let is_changed = match index.get(&relative_path) {
+            Some(Some(existing_hash)) => existing_hash != &hash,
+            Some(None) => true,
             None => match committed_tree.get(&relative_path) {
-                Some(commit_hash) => commit_hash != &blob.hash,
+                Some(commit_hash) => commit_hash != &hash,
                 None => true,
             },
         };
 
-        if !is_changed {
+        if !is_changed && !merging {
             continue;
         }
 
-        let object_path = repo.objects_dir().join(&blob.hash);
-        if !object_path.exists() {
-            fs::write(&object_path, &blob.content)?;
-        }
+        let content = fs::read(&full_path).map_err(NubError::IoError)?;
+        let blob_hash: String = repo.write_blob(&content)?;
 
-        index_map.insert(relative_path.clone(), blob.hash.clone());
+        index.stage(relative_path.clone(), blob_hash);
         changed_count += 1;
 
         println!(
@@ -154,22 +161,13 @@ pub fn execute(files: Vec<String>) -> Result<()> {
         );
     }
 
+    if index != cached_index {
+        repo.write_locked_index(&index, index_lock)?;
+    }
+
     if changed_count == 0 {
         println!("{}", "No changes to stage".dimmed());
-        return Ok(());
     }
 
-    let new_index: Vec<Value> = index_map
-        .into_iter()
-        .map(|(path, hash)| {
-            serde_json::json!({
-                "path": path,
-                "hash": hash
-            })
-        })
-        .collect();
-
-    fs::write(index_path, serde_json::to_string_pretty(&new_index)?)?;
-
     Ok(())
 }
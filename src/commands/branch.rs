@@ -0,0 +1,238 @@
+use crate::error::NubError;
+use crate::objects::short_hash;
+use crate::repository::{self, Repository};
+use anyhow::Result;
+use colored::Colorize;
+use std::fs;
+use std::path::PathBuf;
+
+fn validate_name(repo: &Repository, name: &str) -> Result<()> {
+    if !repository::is_valid_ref_name(name) {
+        return Err(NubError::InvalidBranchName(name.to_string()).into());
+    }
+
+    let branch_path: PathBuf = repo.branch_path(name);
+    if branch_path.exists() {
+        return Err(NubError::BranchAlreadyExists(name.to_string()).into());
+    }
+
+    let mut parent: Option<&std::path::Path> = branch_path.parent();
+    while let Some(dir) = parent {
+        if dir == repo.heads_dir() {
+            break;
+        }
+        if dir.is_file() {
+            let existing: String = dir
+                .strip_prefix(repo.heads_dir())
+                .unwrap_or(dir)
+                .to_string_lossy()
+                .replace("\\", "/");
+            return Err(NubError::BranchAlreadyExists(existing).into());
+        }
+        parent = dir.parent();
+    }
+
+    Ok(())
+}
+
+fn list(repo: &Repository) -> Result<()> {
+    let current: Option<String> = repo.current_branch()?;
+
+    if current.is_none()
+        && let Some(hash) = repo.head_commit_hash()?
+    {
+        println!(
+            "* {}",
+            format!("(HEAD detached at {})", short_hash(&hash)).red()
+        );
+    }
+
+    for name in repo.list_branches()? {
+        if current.as_deref() == Some(name.as_str()) {
+            println!("* {}", name.green().bold());
+        } else {
+            println!("  {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+fn create(repo: &Repository, name: &str, start: Option<String>) -> Result<()> {
+    validate_name(repo, name)?;
+
+    let start: String = start.unwrap_or_else(|| "HEAD".to_string());
+    let start_hash: String = repo.resolve_commit(&start)?;
+
+    repo.write_branch(
+        name,
+        &start_hash,
+        &format!("branch: Created from {}", start),
+    )?;
+
+    println!(
+        "{} Created branch {} at {}",
+        "✓".green().bold(),
+        name.cyan(),
+        short_hash(&start_hash).yellow()
+    );
+    Ok(())
+}
+
+fn rename(repo: &Repository, old: &str, new: &str) -> Result<()> {
+    let hash: String = repo
+        .read_branch(old)?
+        .ok_or_else(|| NubError::BranchNotFound(old.to_string()))?;
+
+    validate_name(repo, new)?;
+
+    let reason: String = format!("Branch: renamed refs/heads/{} to refs/heads/{}", old, new);
+    repo.move_reflog(
+        &format!("refs/heads/{}", old),
+        &format!("refs/heads/{}", new),
+    )?;
+    repo.write_branch(new, &hash, &reason)?;
+    repo.delete_branch(old)?;
+
+    if repo.current_branch()?.as_deref() == Some(old) {
+        repo.set_head_branch(new, &reason)?;
+    }
+
+    println!(
+        "{} Renamed branch {} to {}",
+        "✓".green().bold(),
+        old.cyan(),
+        new.cyan()
+    );
+    Ok(())
+}
+
+fn delete(repo: &Repository, name: &str, force: bool) -> Result<()> {
+    let hash: String = repo
+        .read_branch(name)?
+        .ok_or_else(|| NubError::BranchNotFound(name.to_string()))?;
+
+    if repo.current_branch()?.as_deref() == Some(name) {
+        return Err(NubError::CannotDeleteCurrentBranch(name.to_string()).into());
+    }
+
+    if !force {
+        let merged: bool = match repo.head_commit_hash()? {
+            Some(head) => repo.is_ancestor(&hash, &head)?,
+            None => false,
+        };
+        if !merged {
+            return Err(NubError::BranchNotMerged(name.to_string()).into());
+        }
+    }
+
+    repo.delete_branch(name)?;
+
+    println!(
+        "{} Deleted branch {} (was {})",
+        "✓".green().bold(),
+        name.cyan(),
+        short_hash(&hash).yellow()
+    );
+    Ok(())
+}
+
+pub fn execute(
+    name: Option<String>,
+    start: Option<String>,
+    rename_branch: bool,
+    delete_branch: bool,
+    force_delete: bool,
+) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    fs::create_dir_all(repo.heads_dir())?;
+
+    // clap requires a name for -d, -D and -m.
+    match (name, start) {
+        (Some(name), _) if delete_branch || force_delete => delete(&repo, &name, force_delete),
+        (Some(old), Some(new)) if rename_branch => rename(&repo, &old, &new),
+        (Some(new), None) if rename_branch => {
+            let current: String = repo
+                .current_branch()?
+                .ok_or_else(|| NubError::BranchNotFound("HEAD".to_string()))?;
+            rename(&repo, &current, &new)
+        }
+        (Some(name), start) => create(&repo, &name, start),
+        (None, _) => list(&repo),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::objects::Object;
+    use crate::objects::commit::{Author, Commit};
+    use clap::Parser;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn write_commit(repo: &Repository, parents: Vec<String>) -> String {
+        let tree: String = repo.write_tree_from_files(&HashMap::new()).unwrap();
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let commit: Commit = Commit::new(tree, parents, author, "commit".to_string());
+        repo.write_object(&Object::Commit(commit)).unwrap()
+    }
+
+    #[test]
+    fn test_create_rename_and_delete() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let first: String = write_commit(&repo, Vec::new());
+        repo.write_branch("main", &first, "commit").unwrap();
+
+        create(&repo, "topic", None).unwrap();
+        assert_eq!(repo.read_branch("topic").unwrap(), Some(first.clone()));
+        assert!(create(&repo, "topic", None).is_err());
+        assert!(create(&repo, "bad..name", None).is_err());
+
+        rename(&repo, "topic", "feature").unwrap();
+        assert_eq!(repo.read_branch("topic").unwrap(), None);
+        assert_eq!(repo.read_branch("feature").unwrap(), Some(first.clone()));
+
+        rename(&repo, "main", "trunk").unwrap();
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("trunk"));
+        assert!(delete(&repo, "trunk", true).is_err());
+
+        delete(&repo, "feature", false).unwrap();
+        assert_eq!(repo.read_branch("feature").unwrap(), None);
+        assert!(delete(&repo, "feature", false).is_err());
+    }
+
+    #[test]
+    fn test_unmerged_branch_needs_force() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let first: String = write_commit(&repo, Vec::new());
+        let ahead: String = write_commit(&repo, vec![first.clone()]);
+        repo.write_branch("main", &first, "commit").unwrap();
+        repo.write_branch("topic", &ahead, "branch").unwrap();
+
+        let err: anyhow::Error = delete(&repo, "topic", false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::BranchNotMerged(name)) if name == "topic"
+        ));
+        assert_eq!(repo.read_branch("topic").unwrap(), Some(ahead));
+
+        delete(&repo, "topic", true).unwrap();
+        assert_eq!(repo.read_branch("topic").unwrap(), None);
+    }
+
+    #[test]
+    fn test_name_is_required_for_delete_and_rename() {
+        for flag in ["-d", "-D", "-m"] {
+            assert!(Cli::try_parse_from(["nub", "branch", flag]).is_err());
+            assert!(Cli::try_parse_from(["nub", "branch", flag, "topic"]).is_ok());
+        }
+        assert!(Cli::try_parse_from(["nub", "branch"]).is_ok());
+    }
+}
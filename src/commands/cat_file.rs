@@ -0,0 +1,66 @@
+use crate::error::NubError;
+use crate::objects::tree::EntryType;
+use crate::objects::{Commit, ObjectType, Tag, Tree};
+use crate::repository::Repository;
+use anyhow::Result;
+use std::io::{self, Write};
+
+pub fn execute(object: String, show_type: bool, show_size: bool, pretty: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let hash: String = repo.resolve_object(&object)?;
+    let (kind, body) = repo.read_object(&hash)?;
+
+    if show_type {
+        println!("{}", kind);
+    } else if show_size {
+        println!("{}", body.len());
+    } else if pretty {
+        match kind {
+            ObjectType::Blob => io::stdout().write_all(&body)?,
+            ObjectType::Tree => {
+                let tree: Tree = serde_json::from_slice(&body)
+                    .map_err(|_| NubError::CorruptObject(hash.clone()))?;
+                for entry in &tree.entries {
+                    let entry_kind: &str = match entry.entry_type {
+                        EntryType::Blob => "blob",
+                        EntryType::Tree => "tree",
+                    };
+                    println!("{} {}\t{}", entry_kind, entry.hash, entry.name);
+                }
+            }
+            ObjectType::Commit => {
+                let commit: Commit = serde_json::from_slice(&body)
+                    .map_err(|_| NubError::CorruptObject(hash.clone()))?;
+                println!("tree {}", commit.tree);
+                for parent in &commit.parents {
+                    println!("parent {}", parent);
+                }
+                println!(
+                    "author {} <{}> {}",
+                    commit.author.name,
+                    commit.author.email,
+                    commit.timestamp.to_rfc3339()
+                );
+                println!();
+                println!("{}", commit.message);
+            }
+            ObjectType::Tag => {
+                let tag: Tag = serde_json::from_slice(&body)
+                    .map_err(|_| NubError::CorruptObject(hash.clone()))?;
+                println!("object {}", tag.object);
+                println!("type {}", tag.object_type);
+                println!("tag {}", tag.tag);
+                println!(
+                    "tagger {} <{}> {}",
+                    tag.tagger.name,
+                    tag.tagger.email,
+                    tag.timestamp.to_rfc3339()
+                );
+                println!();
+                println!("{}", tag.message);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::ignore::IgnoreRules;
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+
+pub fn execute(paths: Vec<String>) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let mut ignore: IgnoreRules = repo.ignore_rules();
+
+    for arg in &paths {
+        let spec: String = worktree::repo_relative(&repo, arg)?;
+        if spec.is_empty() {
+            println!("{} {}", arg.cyan(), "is not ignored".dimmed());
+            continue;
+        }
+
+        let is_dir: bool = repo.root.join(&spec).is_dir();
+        match ignore.check(&spec, is_dir) {
+            Some(rule) if !rule.negated => println!(
+                "{} {} by {}:{}:{}",
+                arg.cyan(),
+                "is ignored".red(),
+                rule.source,
+                rule.line,
+                rule.text.yellow()
+            ),
+            Some(rule) => println!(
+                "{} {} by {}:{}:{}",
+                arg.cyan(),
+                "is re-included".green(),
+                rule.source,
+                rule.line,
+                rule.text.yellow()
+            ),
+            None => println!("{} {}", arg.cyan(), "is not ignored".dimmed()),
+        }
+    }
+
+    Ok(())
+}
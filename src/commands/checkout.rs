@@ -0,0 +1,254 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::objects::{Commit, short_hash};
+use crate::repository::{Head, Repository};
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+
+pub fn execute(target: String, force: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    switch(&repo, target, force)
+}
+
+fn switch(repo: &Repository, target: String, force: bool) -> Result<()> {
+    let is_branch: bool = repo.read_branch(&target)?.is_some();
+    let target_hash: String = repo.resolve_commit(&target)?;
+    let from: String = match repo.read_head()? {
+        Head::Branch(name) => name,
+        Head::Detached(hash) => short_hash(&hash).to_string(),
+    };
+    let reason: String = format!("checkout: moving from {} to {}", from, target);
+
+    let current_files: HashMap<String, String> = repo.head_files()?;
+    let target_files: HashMap<String, String> = repo.commit_files(&target_hash)?;
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+
+    let current_tree: Option<String> = repo.head_commit()?.map(|commit: Commit| commit.tree);
+    let target_tree: String = repo.read_commit(&target_hash)?.tree;
+
+    let mut paths: BTreeSet<String> = BTreeSet::new();
+    if force {
+        paths.extend(current_files.keys().cloned());
+        paths.extend(target_files.keys().cloned());
+    } else {
+        for change in repo.diff_trees(current_tree.as_deref(), Some(&target_tree))? {
+            paths.insert(change.path);
+        }
+    }
+    paths.extend(index.paths().cloned());
+
+    let mut conflicts: Vec<String> = Vec::new();
+    let mut kept: Vec<String> = Vec::new();
+
+    for path in &paths {
+        let committed: Option<&String> = current_files.get(path);
+        let wanted: Option<&String> = target_files.get(path);
+        let staged: Option<Option<&String>> = index.get(path);
+        let expected: Option<&String> = staged.unwrap_or(committed);
+        let working: Option<String> = worktree::hash_file(repo, path);
+
+        let staged_change: bool = staged.is_some_and(|hash: Option<&String>| hash != committed);
+        let local_change: bool = working.as_ref() != expected;
+
+        if !staged_change && !local_change {
+            continue;
+        }
+
+        let staged_deletion: bool = staged == Some(None);
+        let untracked: bool = expected.is_none() && !staged_deletion;
+        if untracked && working.as_ref() == wanted {
+            continue;
+        }
+
+        if committed != wanted || untracked {
+            conflicts.push(path.clone());
+        } else {
+            kept.push(path.clone());
+        }
+    }
+
+    if !conflicts.is_empty() && !force {
+        return Err(NubError::WouldOverwrite(conflicts).into());
+    }
+
+    for path in &paths {
+        if !force && kept.contains(path) {
+            continue;
+        }
+
+        match target_files.get(path) {
+            Some(hash) => {
+                if worktree::hash_file(repo, path).as_ref() != Some(hash) {
+                    let content: Vec<u8> = repo.read_blob(hash)?;
+                    worktree::write_file(repo, path, &content)?;
+                }
+            }
+            None => {
+                let tracked: bool = current_files.contains_key(path) || index.contains(path);
+                if tracked {
+                    worktree::remove_file(repo, path)?;
+                }
+            }
+        }
+    }
+
+    if force {
+        index.clear();
+    } else {
+        index.retain(|path: &String| kept.contains(path));
+    }
+    repo.write_locked_index(&index, index_lock)?;
+
+    if is_branch {
+        repo.set_head_branch(&target, &reason)?;
+        println!(
+            "{} Switched to branch {}",
+            "✓".green().bold(),
+            target.cyan()
+        );
+    } else {
+        repo.set_head_detached(&target_hash, &reason)?;
+        println!(
+            "{} HEAD is now at {} (detached)",
+            "✓".green().bold(),
+            short_hash(&target_hash).cyan()
+        );
+    }
+
+    if !force {
+        for path in &kept {
+            println!("  {}\t{}", "M".yellow(), path.yellow());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::Author;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_commit(repo: &Repository, files: &[(&str, &str)], parents: Vec<String>) -> String {
+        let mut tree: HashMap<String, String> = HashMap::new();
+        for (path, content) in files {
+            fs::write(repo.root.join(path), content).unwrap();
+            tree.insert(
+                path.to_string(),
+                repo.write_blob(content.as_bytes()).unwrap(),
+            );
+        }
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree_hash: String = repo.write_tree_from_files(&tree).unwrap();
+        let commit: Commit = Commit::new(tree_hash, parents, author, "commit".to_string());
+        repo.write_object(&Object::Commit(commit)).unwrap()
+    }
+
+    #[test]
+    fn test_staged_deletion_is_carried_across_checkout() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let first: String = write_commit(&repo, &[("a.txt", "a"), ("b.txt", "b")], Vec::new());
+        let other: String = write_commit(
+            &repo,
+            &[("a.txt", "a2"), ("b.txt", "b")],
+            vec![first.clone()],
+        );
+        fs::write(repo.root.join("a.txt"), "a").unwrap();
+        repo.write_branch("main", &first, "commit").unwrap();
+        repo.write_branch("other", &other, "branch").unwrap();
+
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage_deletion("b.txt".to_string());
+        repo.write_index(&index).unwrap();
+        fs::remove_file(repo.root.join("b.txt")).unwrap();
+
+        switch(&repo, "other".to_string(), false).unwrap();
+
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("other"));
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+        assert!(!repo.root.join("b.txt").exists());
+        assert_eq!(repo.read_index().unwrap().get("b.txt"), Some(None));
+    }
+
+    fn two_branches(repo: &Repository) -> (String, String) {
+        let first: String = write_commit(repo, &[("a.txt", "a"), ("b.txt", "b")], Vec::new());
+        fs::remove_file(repo.root.join("b.txt")).unwrap();
+        let other: String = write_commit(
+            repo,
+            &[("a.txt", "a2"), ("c.txt", "c")],
+            vec![first.clone()],
+        );
+        fs::write(repo.root.join("a.txt"), "a").unwrap();
+        fs::write(repo.root.join("b.txt"), "b").unwrap();
+        fs::remove_file(repo.root.join("c.txt")).unwrap();
+        repo.write_branch("main", &first, "commit").unwrap();
+        repo.write_branch("other", &other, "branch").unwrap();
+        (first, other)
+    }
+
+    #[test]
+    fn test_clean_switch_updates_worktree() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        two_branches(&repo);
+
+        switch(&repo, "other".to_string(), false).unwrap();
+
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("other"));
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+        assert_eq!(fs::read_to_string(repo.root.join("c.txt")).unwrap(), "c");
+        assert!(!repo.root.join("b.txt").exists());
+        assert!(repo.read_index().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dirty_file_blocks_switch_without_force() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        two_branches(&repo);
+        fs::write(repo.root.join("a.txt"), "local edit").unwrap();
+
+        let err: anyhow::Error = switch(&repo, "other".to_string(), false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::WouldOverwrite(paths)) if paths == &vec!["a.txt".to_string()]
+        ));
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("main"));
+        assert_eq!(
+            fs::read_to_string(repo.root.join("a.txt")).unwrap(),
+            "local edit"
+        );
+
+        switch(&repo, "other".to_string(), true).unwrap();
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("other"));
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+    }
+
+    #[test]
+    fn test_checkout_commit_detaches_head() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let (_, other) = two_branches(&repo);
+
+        switch(&repo, other.clone(), false).unwrap();
+
+        assert!(matches!(repo.read_head().unwrap(), Head::Detached(hash) if hash == other));
+        assert_eq!(repo.current_branch().unwrap(), None);
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+
+        switch(&repo, "main".to_string(), false).unwrap();
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("main"));
+        assert_eq!(fs::read_to_string(repo.root.join("b.txt")).unwrap(), "b");
+    }
+}
@@ -1,11 +1,11 @@
 use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::objects::Object;
 use crate::objects::commit::{Author, Commit};
-use crate::objects::tree::{EntryType, Tree};
 use crate::repository::Repository;
 use anyhow::Result;
 use colored::Colorize;
-use serde_json::Value;
-use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
@@ -18,125 +18,71 @@ pub fn execute(message: String) -> Result<()> {
         return Err(NubError::InvalidRepository.into());
     }
 
-    let index_data: String = fs::read_to_string(&index_path)?;
-    let index: Vec<Value> = serde_json::from_str(&index_data).unwrap_or_default();
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
 
-    if index.is_empty() {
+    let merge_head: Option<String> = if repo.merge_head_path().exists() {
+        Some(
+            fs::read_to_string(repo.merge_head_path())?
+                .trim()
+                .to_string(),
+        )
+    } else {
+        None
+    };
+
+    if index.is_empty() && merge_head.is_none() {
         eprintln!("{} Nothing to commit", "✗".red().bold());
         return Ok(());
     }
 
-    let head_ref: String = fs::read_to_string(repo.head_path())?;
-    let head_ref_path: &str = head_ref.trim_start_matches("ref: ").trim();
-    let current_branch: PathBuf = repo.nub_dir.join(head_ref_path);
-
-    if let Some(parent) = current_branch.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let parent_hash: Option<String> = if current_branch.exists() {
-        Some(fs::read_to_string(&current_branch)?.trim().to_string())
-    } else {
-        None
-    };
+    let parent_hash: Option<String> = repo.head_commit_hash()?;
 
-    let mut all_files: HashMap<String, String> = if let Some(ref parent) = parent_hash {
-        let commit_path = repo.commits_dir().join(parent);
-        if commit_path.exists() {
-            let commit_data = fs::read_to_string(commit_path)?;
-            let commit_json: Value = serde_json::from_str(&commit_data)?;
-
-            if let Some(tree_hash) = commit_json.get("tree").and_then(|v| v.as_str()) {
-                let tree_path = repo.objects_dir().join(tree_hash);
-                if tree_path.exists() {
-                    let tree_data = fs::read_to_string(tree_path)?;
-                    let tree_json: Value = serde_json::from_str(&tree_data)?;
-
-                    tree_json["entries"]
-                        .as_array()
-                        .unwrap_or(&vec![])
-                        .iter()
-                        .filter_map(|e| {
-                            Some((
-                                e.get("name")?.as_str()?.to_string(),
-                                e.get("hash")?.as_str()?.to_string(),
-                            ))
-                        })
-                        .collect()
-                } else {
-                    HashMap::new()
-                }
-            } else {
-                HashMap::new()
-            }
-        } else {
-            HashMap::new()
-        }
-    } else {
-        HashMap::new()
+    let mut all_files: HashMap<String, String> = match &parent_hash {
+        Some(parent) => repo.commit_files(parent)?,
+        None => HashMap::new(),
     };
 
-    for entry in &index {
-        if let (Some(path), Some(hash)) = (entry.get("path"), entry.get("hash")) {
-            all_files.insert(
-                path.as_str().unwrap().to_string(),
-                hash.as_str().unwrap().to_string(),
-            );
+    index.apply_to(&mut all_files);
+
+    if merge_head.is_some() {
+        let merge_msg: String = fs::read_to_string(repo.merge_msg_path()).unwrap_or_default();
+        let unresolved: Vec<String> = merge_msg
+            .lines()
+            .filter_map(|line: &str| line.strip_prefix("#\t"))
+            .filter(|path: &&str| !index.contains(path))
+            .map(|path: &str| path.to_string())
+            .collect();
+        if !unresolved.is_empty() {
+            return Err(NubError::UnresolvedConflicts(unresolved).into());
         }
     }
 
-    let mut tree: Tree = Tree::new();
-    for (path, hash) in all_files {
-        tree.add_entry(path, hash, EntryType::Blob);
-    }
-
-    let tree_json: String = serde_json::to_string(&tree)?;
-    let mut tree_hasher = Sha256::new();
-    tree_hasher.update(tree_json.as_bytes());
-    let tree_hash: String = format!("{:x}", tree_hasher.finalize());
+    let tree_hash: String = repo.write_tree_from_files(&all_files)?;
 
-    let tree_path: PathBuf = repo.objects_dir().join(&tree_hash);
-    fs::write(tree_path, &tree_json)?;
+    let author: Author = repo.config()?.identity();
 
-    let head_ref: String = fs::read_to_string(repo.head_path())?;
-    let head_ref_path: &str = head_ref.trim_start_matches("ref: ").trim();
+    let is_merge: bool = merge_head.is_some();
+    let parents: Vec<String> = parent_hash.iter().cloned().chain(merge_head).collect();
+    let commit: Commit = Commit::new(tree_hash.clone(), parents, author, message.clone());
+    let commit_hash: String = repo.write_object(&Object::Commit(commit))?;
 
-    let current_branch: PathBuf = repo.nub_dir.join(head_ref_path);
-
-    if let Some(parent) = current_branch.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let parent_hash: Option<String> = if current_branch.exists() {
-        Some(fs::read_to_string(&current_branch)?.trim().to_string())
-    } else {
-        None
+    let kind: &str = match (&parent_hash, is_merge) {
+        (None, _) => "commit (initial)",
+        (Some(_), true) => "commit (merge)",
+        (Some(_), false) => "commit",
     };
+    let reason: String = format!("{}: {}", kind, message);
+    repo.update_head(&commit_hash, parent_hash.as_deref(), &reason)?;
 
-    let config_data: String = fs::read_to_string(repo.config_path())?;
-    let config_json: Value = serde_json::from_str(&config_data)?;
-    let user: &Value = config_json.get("user").unwrap();
-    let author_name: String = user.get("name").unwrap().as_str().unwrap().to_string();
-    let author_email: String = user.get("email").unwrap().as_str().unwrap().to_string();
-
-    let author = Author {
-        name: author_name,
-        email: author_email,
-    };
+    index.clear();
+    repo.write_locked_index(&index, index_lock)?;
 
-    let commit: Commit = Commit::new(tree_hash.clone(), parent_hash, author, message.clone());
-    let commit_json: String = serde_json::to_string_pretty(&commit)?;
-
-    let mut hasher = Sha256::new();
-    hasher.update(commit_json.as_bytes());
-    let commit_hash: String = format!("{:x}", hasher.finalize());
-
-    let commit_path = repo.commits_dir().join(&commit_hash);
-    fs::write(&commit_path, &commit_json)?;
-
-    fs::write(&current_branch, &commit_hash)?;
-
-    fs::write(index_path, "[]")?;
+    for state_path in [repo.merge_head_path(), repo.merge_msg_path()] {
+        if state_path.exists() {
+            fs::remove_file(state_path)?;
+        }
+    }
 
     println!(
         "{} Created commit {}",
@@ -0,0 +1,96 @@
+use crate::config::{self, Config, ConfigValue, Level};
+use crate::error::NubError;
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+pub enum Action {
+    Get(String),
+    Set(String, String),
+    Unset(String),
+    List,
+}
+
+fn local_config() -> Option<PathBuf> {
+    Repository::find_unchecked()
+        .ok()
+        .map(|repo: Repository| repo.config_path())
+}
+
+fn load(level: Option<Level>, local: Option<&Path>) -> Result<Config> {
+    match level {
+        Some(level) => {
+            let files: Vec<(Level, PathBuf)> = level
+                .path(local)
+                .map(|path: PathBuf| vec![(level, path)])
+                .unwrap_or_default();
+            Config::load_from(&files, Vec::new())
+        }
+        None => Config::load(local),
+    }
+}
+
+fn target_path(level: Option<Level>, local: Option<&Path>) -> Result<PathBuf> {
+    let level: Level = level.unwrap_or(Level::Local);
+    let path: Option<PathBuf> = level.path(local);
+    match (path, level) {
+        (Some(path), _) => Ok(path),
+        (None, Level::Global) => Err(NubError::NoHomeDirectory.into()),
+        (None, _) => Err(NubError::RepositoryNotFound.into()),
+    }
+}
+
+fn print_value(key: &str, value: &ConfigValue, show_origin: bool, with_key: bool) {
+    if show_origin {
+        print!("{}\t", value.origin.to_string().dimmed());
+    }
+    if with_key {
+        println!("{}={}", key, value.value);
+    } else {
+        println!("{}", value.value);
+    }
+}
+
+pub fn execute(action: Action, level: Option<Level>, show_origin: bool) -> Result<()> {
+    let local: Option<PathBuf> = local_config();
+
+    match action {
+        Action::Get(key) => {
+            let key: String = config::normalize_key(&key)?;
+            let config: Config = load(level, local.as_deref())?;
+            let value: &ConfigValue = config
+                .get(&key)
+                .ok_or_else(|| NubError::MissingConfig(key.clone()))?;
+            print_value(&key, value, show_origin, false);
+        }
+        Action::List => {
+            let config: Config = load(level, local.as_deref())?;
+            for (key, value) in config.entries() {
+                print_value(key, value, show_origin, true);
+            }
+        }
+        Action::Set(key, value) => {
+            let path: PathBuf = target_path(level, local.as_deref())?;
+            config::set_value(&path, &key, &value)?;
+            println!(
+                "{} Set {} in {}",
+                "✓".green().bold(),
+                key.cyan(),
+                path.display().to_string().cyan()
+            );
+        }
+        Action::Unset(key) => {
+            let path: PathBuf = target_path(level, local.as_deref())?;
+            config::unset_value(&path, &key)?;
+            println!(
+                "{} Unset {} in {}",
+                "✓".green().bold(),
+                key.cyan(),
+                path.display().to_string().cyan()
+            );
+        }
+    }
+
+    Ok(())
+}
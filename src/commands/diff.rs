@@ -0,0 +1,66 @@
+use crate::diff;
+use crate::index::Index;
+use crate::repository::Repository;
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+pub fn execute(commit: Option<String>, staged: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let index: Index = repo.read_index()?;
+    let committed_tree: HashMap<String, String> = repo.head_files()?;
+    let base: Option<HashMap<String, String>> = match commit {
+        Some(rev) => Some(repo.commit_files(&repo.resolve_commit(&rev)?)?),
+        None => None,
+    };
+
+    let mut tracked: HashMap<String, String> = committed_tree.clone();
+    index.apply_to(&mut tracked);
+
+    if staged {
+        let base: HashMap<String, String> = base.unwrap_or(committed_tree);
+        let paths: BTreeSet<&String> = base.keys().chain(tracked.keys()).collect();
+        for path in paths {
+            let committed: Option<&String> = base.get(path);
+            let index_hash: Option<&String> = tracked.get(path);
+            if committed == index_hash {
+                continue;
+            }
+            let old: Option<Vec<u8>> = match committed {
+                Some(commit_hash) => Some(repo.read_blob(commit_hash)?),
+                None => None,
+            };
+            let new: Option<Vec<u8>> = match index_hash {
+                Some(hash) => Some(repo.read_blob(hash)?),
+                None => None,
+            };
+            diff::print_file_diff(path, old.as_deref(), new.as_deref());
+        }
+        return Ok(());
+    }
+
+    let base: &HashMap<String, String> = base.as_ref().unwrap_or(&tracked);
+    let paths: BTreeSet<&String> = base.keys().chain(tracked.keys()).collect();
+    for path in paths {
+        let full_path: PathBuf = repo.root.join(path);
+        let blob: Option<Vec<u8>> = match base.get(path) {
+            Some(hash) => Some(repo.read_blob(hash)?),
+            None => None,
+        };
+
+        if !tracked.contains_key(path) || !full_path.is_file() {
+            if blob.is_some() {
+                diff::print_file_diff(path, blob.as_deref(), None);
+            }
+            continue;
+        }
+
+        let working: Vec<u8> = fs::read(&full_path)?;
+        if blob.as_ref() != Some(&working) {
+            diff::print_file_diff(path, blob.as_deref(), Some(&working));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use crate::error::NubError;
+use crate::fsck::{self, FsckReport};
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+
+pub fn execute() -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let report: FsckReport = fsck::check(&repo)?;
+
+    for problem in &report.problems {
+        println!("{} {}", "✗".red().bold(), problem);
+    }
+    for (kind, hash) in &report.dangling {
+        println!("{} {} {}", "dangling".dimmed(), kind, hash.yellow());
+    }
+    for (kind, hash) in &report.unreachable {
+        println!("{} {} {}", "unreachable".dimmed(), kind, hash.yellow());
+    }
+
+    if !report.problems.is_empty() {
+        return Err(NubError::IntegrityCheckFailed(report.problems.len()).into());
+    }
+
+    println!(
+        "{} Checked {} objects, no problems found",
+        "✓".green().bold(),
+        report.checked.to_string().yellow()
+    );
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use crate::error::NubError;
+use crate::object_store::ObjectStore;
+use crate::objects::{ObjectType, hash_object};
+use crate::pack::{Pack, PackObject, PackStats};
+use crate::prune;
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+pub fn execute() -> Result<()> {
+    let mut repo: Repository = Repository::find()?;
+    let objects: &ObjectStore = repo.objects();
+    let grace: Duration = prune::grace_period(&repo, None)?;
+
+    let wanted: BTreeMap<String, String> = repo.reachable_objects()?;
+    let old_packs: Vec<String> = objects
+        .packs()?
+        .iter()
+        .map(|p: &Pack| p.name.clone())
+        .collect();
+
+    // Unreachable objects in recent packs are loosened with the pack's mtime
+    // so the prune step below expires them on the same schedule.
+    let mut loosened: usize = 0;
+    for pack in objects.packs()? {
+        let modified: SystemTime = objects.pack_modified(&pack.name)?;
+        if prune::is_expired(modified, grace) {
+            continue;
+        }
+        for hash in pack.hashes() {
+            if wanted.contains_key(&hash) || objects.contains_loose(&hash) {
+                continue;
+            }
+            if let Some(data) = pack.read(&hash)? {
+                objects.write(&hash, &data)?;
+                objects.set_loose_modified(&hash, modified)?;
+                loosened += 1;
+            }
+        }
+    }
+
+    repack(&mut repo, wanted, &old_packs)?;
+    let pruned: Vec<String> = prune::prune(&repo, grace)?;
+
+    if loosened > 0 {
+        println!(
+            "  Unpacked {} recent unreachable objects",
+            loosened.to_string().yellow()
+        );
+    }
+    if !pruned.is_empty() {
+        println!(
+            "  Pruned {} unreachable loose objects",
+            pruned.len().to_string().yellow()
+        );
+    }
+
+    Ok(())
+}
+
+fn repack(
+    repo: &mut Repository,
+    wanted: BTreeMap<String, String>,
+    old_packs: &[String],
+) -> Result<()> {
+    if wanted.is_empty() {
+        let objects: &mut ObjectStore = repo.objects_mut();
+        for old in old_packs {
+            objects.remove_pack(old)?;
+        }
+        println!("{}", "Nothing to pack".dimmed());
+        return Ok(());
+    }
+
+    let mut pack_objects: Vec<PackObject> = Vec::new();
+    for (hash, name_hint) in wanted {
+        let (kind, body): (ObjectType, Vec<u8>) = repo.read_object(&hash)?;
+        if hash_object(kind, &body) != hash {
+            return Err(NubError::CorruptObject(hash).into());
+        }
+        pack_objects.push(PackObject {
+            hash,
+            kind,
+            body,
+            name_hint,
+        });
+    }
+
+    let objects: &mut ObjectStore = repo.objects_mut();
+    let (name, stats): (String, PackStats) = objects.write_pack(&pack_objects)?;
+
+    for old in old_packs {
+        if old != &name {
+            objects.remove_pack(old)?;
+        }
+    }
+
+    let packed: HashSet<&String> = pack_objects.iter().map(|o: &PackObject| &o.hash).collect();
+    for hash in objects.loose_hashes()? {
+        if packed.contains(&hash) {
+            objects.remove_loose(&hash)?;
+        }
+    }
+
+    println!(
+        "{} Packed {} objects ({} deltas) into {}",
+        "✓".green().bold(),
+        stats.objects.to_string().yellow(),
+        stats.deltas.to_string().yellow(),
+        name.cyan()
+    );
+    Ok(())
+}
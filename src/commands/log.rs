@@ -0,0 +1,152 @@
+use crate::objects::{Commit, short_hash};
+use crate::repository::Repository;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use std::collections::{BinaryHeap, HashSet};
+
+pub fn format_date(timestamp: &DateTime<Utc>) -> String {
+    timestamp
+        .with_timezone(&Local)
+        .format("%a %b %e %H:%M:%S %Y %z")
+        .to_string()
+}
+
+/// Formats the commit header shared by `log` and `show`; `show_parent` adds a
+/// `Parent:` line for ordinary commits, since only merges list their parents.
+pub fn commit_header(hash: &str, commit: &Commit, show_parent: bool) -> String {
+    let mut header: String = format!("{} {}\n", "commit".yellow(), hash.yellow());
+    if show_parent && let [parent] = commit.parents.as_slice() {
+        header.push_str(&format!("Parent: {}\n", short_hash(parent)));
+    }
+    if commit.is_merge() {
+        let parents: Vec<&str> = commit
+            .parents
+            .iter()
+            .map(|p: &String| short_hash(p))
+            .collect();
+        header.push_str(&format!("Merge:  {}\n", parents.join(" ")));
+    }
+    header.push_str(&format!(
+        "Author: {} <{}>\n",
+        commit.author.name, commit.author.email
+    ));
+    header.push_str(&format!("Date:   {}\n\n", format_date(&commit.timestamp)));
+    for line in commit.message.lines() {
+        header.push_str(&format!("    {}\n", line));
+    }
+    header
+}
+
+/// Visits commits reachable from `head`, newest first, each exactly once.
+fn walk(
+    repo: &Repository,
+    head: String,
+    count: Option<usize>,
+    mut visit: impl FnMut(&str, &Commit),
+) -> Result<()> {
+    let mut queue: BinaryHeap<(DateTime<Utc>, String)> = BinaryHeap::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    queue.push((repo.read_commit(&head)?.timestamp, head.clone()));
+    seen.insert(head);
+
+    let mut shown: usize = 0;
+    while let Some((_, hash)) = queue.pop() {
+        if count.is_some_and(|limit: usize| shown >= limit) {
+            break;
+        }
+
+        let commit: Commit = repo.read_commit(&hash)?;
+        visit(&hash, &commit);
+
+        for parent in &commit.parents {
+            if seen.insert(parent.clone()) {
+                queue.push((repo.read_commit(parent)?.timestamp, parent.clone()));
+            }
+        }
+        shown += 1;
+    }
+
+    Ok(())
+}
+
+pub fn execute(revision: Option<String>, count: Option<usize>, oneline: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+
+    let start: Option<String> = match revision {
+        Some(rev) => Some(repo.resolve_commit(&rev)?),
+        None => repo.head_commit_hash()?,
+    };
+    let Some(head) = start else {
+        let branch: String = repo.current_branch()?.unwrap_or_default();
+        println!(
+            "{} Branch {} has no commits yet",
+            "✗".red().bold(),
+            branch.cyan()
+        );
+        return Ok(());
+    };
+
+    walk(&repo, head, count, |hash: &str, commit: &Commit| {
+        if oneline {
+            let summary: &str = commit.message.lines().next().unwrap_or("");
+            println!("{} {}", short_hash(hash).yellow(), summary);
+        } else {
+            println!("{}", commit_header(hash, commit, false));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::Author;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn write_commit(repo: &Repository, parents: Vec<String>, day: u32, message: &str) -> String {
+        let tree: String = repo.write_tree_from_files(&HashMap::new()).unwrap();
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let mut commit: Commit = Commit::new(tree, parents, author, message.to_string());
+        commit.timestamp = Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        repo.write_object(&Object::Commit(commit)).unwrap()
+    }
+
+    #[test]
+    fn test_walk_orders_merged_history_by_date() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let root: String = write_commit(&repo, Vec::new(), 1, "root");
+        let side: String = write_commit(&repo, vec![root.clone()], 2, "side");
+        let main: String = write_commit(&repo, vec![root.clone()], 3, "main");
+        let merge: String = write_commit(&repo, vec![main.clone(), side.clone()], 4, "merge");
+
+        let mut visited: Vec<String> = Vec::new();
+        walk(&repo, merge.clone(), None, |_: &str, commit: &Commit| {
+            visited.push(commit.message.clone())
+        })
+        .unwrap();
+        assert_eq!(visited, vec!["merge", "main", "side", "root"]);
+
+        let mut limited: Vec<String> = Vec::new();
+        walk(&repo, merge.clone(), Some(2), |hash: &str, _: &Commit| {
+            limited.push(hash.to_string())
+        })
+        .unwrap();
+        assert_eq!(limited, vec![merge.clone(), main.clone()]);
+
+        let header: String = commit_header(&merge, &repo.read_commit(&merge).unwrap(), true);
+        assert!(header.contains(&format!("Merge:  {} {}\n", &main[..8], &side[..8])));
+        assert!(header.contains("    merge\n"));
+        assert!(!header.contains("Parent:"));
+        let header: String = commit_header(&main, &repo.read_commit(&main).unwrap(), false);
+        assert!(!header.contains("Merge:") && !header.contains("Parent:"));
+        let header: String = commit_header(&main, &repo.read_commit(&main).unwrap(), true);
+        assert!(header.contains(&format!("Parent: {}\n", &root[..8])));
+    }
+}
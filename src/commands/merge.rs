@@ -0,0 +1,209 @@
+use crate::commands;
+use crate::diff::{self, MergeResult};
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::{self, LockFile};
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+
+pub enum Resolution {
+    Keep,
+    Take(String),
+    Delete,
+    Conflict(&'static str),
+}
+
+pub fn resolve_path(
+    repo: &Repository,
+    path: &str,
+    base: Option<&String>,
+    ours: Option<&String>,
+    theirs: Option<&String>,
+    their_label: &str,
+) -> Result<Resolution> {
+    if ours == theirs || base == theirs {
+        return Ok(Resolution::Keep);
+    }
+    if base == ours {
+        return Ok(match theirs {
+            Some(hash) => Resolution::Take(hash.clone()),
+            None => Resolution::Delete,
+        });
+    }
+
+    let (Some(our_hash), Some(their_hash)) = (ours, theirs) else {
+        if let Some(their_hash) = theirs {
+            let content: Vec<u8> = repo.read_blob(their_hash)?;
+            worktree::write_file(repo, path, &content)?;
+        }
+        return Ok(Resolution::Conflict("modify/delete"));
+    };
+
+    let base_content: Vec<u8> = match base {
+        Some(hash) => repo.read_blob(hash)?,
+        None => Vec::new(),
+    };
+    let our_content: Vec<u8> = repo.read_blob(our_hash)?;
+    let their_content: Vec<u8> = repo.read_blob(their_hash)?;
+
+    if diff::is_binary(&base_content)
+        || diff::is_binary(&our_content)
+        || diff::is_binary(&their_content)
+    {
+        return Ok(Resolution::Conflict("binary"));
+    }
+
+    let result: MergeResult = diff::merge3(
+        &String::from_utf8_lossy(&base_content),
+        &String::from_utf8_lossy(&our_content),
+        &String::from_utf8_lossy(&their_content),
+        "HEAD",
+        their_label,
+    );
+
+    if result.conflicts > 0 {
+        worktree::write_file(repo, path, result.content.as_bytes())?;
+        let kind: &'static str = if base.is_some() { "content" } else { "add/add" };
+        return Ok(Resolution::Conflict(kind));
+    }
+
+    Ok(Resolution::Take(
+        repo.write_blob(result.content.as_bytes())?,
+    ))
+}
+
+pub fn execute(branch: String) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+
+    if repo.merge_head_path().exists() {
+        return Err(NubError::MergeInProgress.into());
+    }
+
+    let ours: String = repo
+        .head_commit_hash()?
+        .ok_or_else(|| NubError::RevisionNotFound("HEAD".to_string()))?;
+    let theirs: String = repo.resolve_commit(&branch)?;
+    let base: Option<String> = repo.merge_base(&ours, &theirs)?;
+
+    if base.as_deref() == Some(theirs.as_str()) {
+        println!("{} Already up to date", "✓".green().bold());
+        return Ok(());
+    }
+
+    let our_files: HashMap<String, String> = repo.commit_files(&ours)?;
+    let their_files: HashMap<String, String> = repo.commit_files(&theirs)?;
+    let base_files: HashMap<String, String> = match &base {
+        Some(hash) => repo.commit_files(hash)?,
+        None => HashMap::new(),
+    };
+
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+    if !index.is_empty() {
+        let staged: Vec<String> = index.paths().cloned().collect();
+        return Err(NubError::WouldOverwrite(staged).into());
+    }
+
+    let base_tree: Option<String> = match &base {
+        Some(hash) => Some(repo.read_commit(hash)?.tree),
+        None => None,
+    };
+    let our_tree: String = repo.read_commit(&ours)?.tree;
+    let their_tree: String = repo.read_commit(&theirs)?.tree;
+
+    let mut paths: BTreeSet<String> = BTreeSet::new();
+    for tree in [&our_tree, &their_tree] {
+        for change in repo.diff_trees(base_tree.as_deref(), Some(tree))? {
+            paths.insert(change.path);
+        }
+    }
+
+    let dirty: Vec<String> = paths
+        .iter()
+        .filter(|path: &&String| our_files.get(*path) != their_files.get(*path))
+        .filter(|path: &&String| worktree::hash_file(&repo, path).as_ref() != our_files.get(*path))
+        .cloned()
+        .collect();
+    if !dirty.is_empty() {
+        return Err(NubError::WouldOverwrite(dirty).into());
+    }
+
+    if base.as_deref() == Some(ours.as_str()) {
+        worktree::switch_files(&repo, &our_files, &their_files)?;
+        repo.update_head(
+            &theirs,
+            Some(&ours),
+            &format!("merge {}: Fast-forward", branch),
+        )?;
+        println!(
+            "{} Fast-forward {}..{}",
+            "✓".green().bold(),
+            ours[..8].yellow(),
+            theirs[..8].yellow()
+        );
+        return Ok(());
+    }
+
+    let mut conflicts: Vec<(String, &'static str)> = Vec::new();
+
+    for path in paths {
+        let resolution: Resolution = resolve_path(
+            &repo,
+            &path,
+            base_files.get(&path),
+            our_files.get(&path),
+            their_files.get(&path),
+            &branch,
+        )?;
+
+        match resolution {
+            Resolution::Keep => {}
+            Resolution::Take(hash) => {
+                let content: Vec<u8> = repo.read_blob(&hash)?;
+                worktree::write_file(&repo, &path, &content)?;
+                index.stage(path, hash);
+            }
+            Resolution::Delete => {
+                worktree::remove_file(&repo, &path)?;
+                index.stage_deletion(path);
+            }
+            Resolution::Conflict(kind) => conflicts.push((path, kind)),
+        }
+    }
+
+    repo.write_locked_index(&index, index_lock)?;
+
+    let message: String = format!("Merge branch '{}'", branch);
+    let mut merge_msg: String = format!("{}\n", message);
+    if !conflicts.is_empty() {
+        merge_msg.push_str("\n# Conflicts:\n");
+        for (path, _) in &conflicts {
+            merge_msg.push_str(&format!("#\t{}\n", path));
+        }
+    }
+    lockfile::write_atomic(&repo.merge_head_path(), theirs.as_bytes())?;
+    lockfile::write_atomic(&repo.merge_msg_path(), merge_msg.as_bytes())?;
+
+    if conflicts.is_empty() {
+        return commands::commit::execute(message);
+    }
+
+    for (path, kind) in &conflicts {
+        println!(
+            "{} Merge conflict ({}) in {}",
+            "✗".red().bold(),
+            kind,
+            path.red()
+        );
+    }
+    println!(
+        "Fix the conflicts, {} the files and run {} to finish the merge",
+        "nub add".cyan(),
+        "nub commit".cyan()
+    );
+
+    Ok(())
+}
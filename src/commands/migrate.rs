@@ -0,0 +1,36 @@
+use crate::migrate::{self, MigrationReport};
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+
+pub fn execute() -> Result<()> {
+    let repo: Repository = Repository::find_unchecked()?;
+
+    if repo.legacy_commits_dir().exists() || migrate::is_pending(&repo) {
+        let report: MigrationReport = migrate::migrate_legacy_layout(&repo)?;
+        println!(
+            "{} Migrated {} commits, {} trees and {} blobs",
+            "✓".green().bold(),
+            report.commits.to_string().yellow(),
+            report.trees.to_string().yellow(),
+            report.blobs.to_string().yellow()
+        );
+        return Ok(());
+    }
+
+    let sharded: usize = repo.objects().shard_flat_objects()?;
+    if sharded > 0 {
+        println!(
+            "{} Compressed {} loose objects into fan-out directories",
+            "✓".green().bold(),
+            sharded.to_string().yellow()
+        );
+    } else {
+        println!(
+            "{} Repository already uses the current object layout",
+            "✓".green().bold()
+        );
+    }
+
+    Ok(())
+}
@@ -1,6 +1,24 @@
 pub mod add;
+pub mod branch;
+pub mod cat_file;
+pub mod check_ignore;
+pub mod checkout;
 pub mod commit;
+pub mod config;
+pub mod diff;
+pub mod fsck;
+pub mod gc;
 pub mod init;
+pub mod log;
+pub mod merge;
+pub mod migrate;
+pub mod mv;
+pub mod prune;
+pub mod reflog;
 pub mod reset;
+pub mod restore;
+pub mod rm;
+pub mod show;
+pub mod stash;
 pub mod status;
-// pub mod log;
+pub mod tag;
@@ -0,0 +1,196 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+fn move_path(repo: &Repository, source: String, destination: String, force: bool) -> Result<()> {
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+    let committed_tree: HashMap<String, String> = repo.head_files()?;
+
+    let mut tracked: HashMap<String, String> = committed_tree.clone();
+    index.apply_to(&mut tracked);
+
+    let src: String = worktree::repo_relative(repo, &source)?;
+    let mut dst: String = worktree::repo_relative(repo, &destination)?;
+
+    if src.is_empty() || dst.is_empty() {
+        return Err(NubError::PathOutsideRepository(source).into());
+    }
+
+    if repo.root.join(&dst).is_dir() {
+        let name: &str = src.rsplit('/').next().unwrap_or(&src);
+        dst = format!("{}/{}", dst, name);
+    }
+
+    if worktree::matches_pathspec(&dst, &src) {
+        return Err(NubError::DestinationExists(dst).into());
+    }
+
+    let mut moves: Vec<(String, String)> = tracked
+        .keys()
+        .filter(|path: &&String| worktree::matches_pathspec(path, &src))
+        .map(|path: &String| (path.clone(), format!("{}{}", dst, &path[src.len()..])))
+        .collect();
+    moves.sort();
+
+    if moves.is_empty() {
+        return Err(NubError::NotTracked(source).into());
+    }
+
+    let src_path: PathBuf = repo.root.join(&src);
+    let dst_path: PathBuf = repo.root.join(&dst);
+
+    if !force {
+        if dst_path.exists() {
+            return Err(NubError::DestinationExists(dst).into());
+        }
+        if let Some((_, target)) = moves.iter().find(|(_, new)| tracked.contains_key(new)) {
+            return Err(NubError::DestinationExists(target.clone()).into());
+        }
+    }
+
+    if src_path.exists() {
+        if let Some(parent) = dst_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if force && dst_path.is_dir() {
+            fs::remove_dir_all(&dst_path)?;
+        }
+        fs::rename(&src_path, &dst_path)?;
+        worktree::remove_file(repo, &src)?;
+    }
+
+    for (old, new) in &moves {
+        let hash: String = tracked[old].clone();
+
+        if committed_tree.contains_key(old) {
+            index.stage_deletion(old.clone());
+        } else {
+            index.unstage(old);
+        }
+        index.stage(new.clone(), hash);
+
+        println!(
+            "{} renamed {} -> {}",
+            "✓".green().bold(),
+            old.cyan(),
+            new.cyan()
+        );
+    }
+
+    repo.write_locked_index(&index, index_lock)?;
+
+    Ok(())
+}
+
+pub fn execute(source: String, destination: String, force: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    move_path(&repo, source, destination, force)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::{Author, Commit};
+    use tempfile::TempDir;
+
+    fn commit_files(repo: &Repository, files: &[(&str, &str)]) {
+        let mut tree: HashMap<String, String> = HashMap::new();
+        for (path, content) in files {
+            worktree::write_file(repo, path, content.as_bytes()).unwrap();
+            tree.insert(
+                path.to_string(),
+                repo.write_blob(content.as_bytes()).unwrap(),
+            );
+        }
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree_hash: String = repo.write_tree_from_files(&tree).unwrap();
+        let commit: Commit = Commit::new(tree_hash, Vec::new(), author, "commit".to_string());
+        let hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+        repo.write_branch("main", &hash, "commit").unwrap();
+    }
+
+    fn absolute(repo: &Repository, path: &str) -> String {
+        repo.root.join(path).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_move_file_stages_rename() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        commit_files(&repo, &[("a.txt", "a")]);
+
+        move_path(
+            &repo,
+            absolute(&repo, "a.txt"),
+            absolute(&repo, "b.txt"),
+            false,
+        )
+        .unwrap();
+
+        assert!(!repo.root.join("a.txt").exists());
+        assert_eq!(fs::read_to_string(repo.root.join("b.txt")).unwrap(), "a");
+        let index: Index = repo.read_index().unwrap();
+        assert_eq!(index.get("a.txt"), Some(None));
+        assert_eq!(
+            index.get("b.txt"),
+            Some(Some(&repo.write_blob(b"a").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_move_directory_moves_every_tracked_file() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        commit_files(&repo, &[("src/a.txt", "a"), ("src/sub/b.txt", "b")]);
+
+        move_path(&repo, absolute(&repo, "src"), absolute(&repo, "lib"), false).unwrap();
+
+        assert!(!repo.root.join("src").exists());
+        assert_eq!(
+            fs::read_to_string(repo.root.join("lib/sub/b.txt")).unwrap(),
+            "b"
+        );
+        let index: Index = repo.read_index().unwrap();
+        for (old, new) in [
+            ("src/a.txt", "lib/a.txt"),
+            ("src/sub/b.txt", "lib/sub/b.txt"),
+        ] {
+            assert_eq!(index.get(old), Some(None));
+            assert!(matches!(index.get(new), Some(Some(_))));
+        }
+    }
+
+    #[test]
+    fn test_existing_destination_is_refused() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        commit_files(&repo, &[("a.txt", "a"), ("b.txt", "b")]);
+
+        let err: anyhow::Error = move_path(
+            &repo,
+            absolute(&repo, "a.txt"),
+            absolute(&repo, "b.txt"),
+            false,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::DestinationExists(path)) if path == "b.txt"
+        ));
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(repo.root.join("b.txt")).unwrap(), "b");
+        assert!(repo.read_index().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,30 @@
+use crate::prune;
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+use std::time::Duration;
+
+pub fn execute(dry_run: bool, expire: Option<String>) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let grace: Duration = prune::grace_period(&repo, expire.as_deref())?;
+
+    if dry_run {
+        let candidates: Vec<String> = prune::candidates(&repo, grace)?;
+        for hash in &candidates {
+            println!("{} {}", "would prune".dimmed(), hash.yellow());
+        }
+        if candidates.is_empty() {
+            println!("{}", "Nothing to prune".dimmed());
+        }
+        return Ok(());
+    }
+
+    let pruned: Vec<String> = prune::prune(&repo, grace)?;
+    println!(
+        "{} Pruned {} unreachable objects",
+        "✓".green().bold(),
+        pruned.len().to_string().yellow()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::error::NubError;
+use crate::prune;
+use crate::reflog::ReflogEntry;
+use crate::repository::Repository;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use std::time::Duration;
+
+const EXPIRE_CONFIG_KEY: &str = "gc.reflogExpire";
+const DEFAULT_EXPIRE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+pub enum Action {
+    Show(Option<String>),
+    Expire {
+        expire: Option<String>,
+        all: bool,
+        refname: Option<String>,
+    },
+}
+
+fn show(repo: &Repository, name: Option<String>) -> Result<()> {
+    let refname: String = repo.reflog_ref_name(name.as_deref().unwrap_or("HEAD"))?;
+    let entries: Vec<ReflogEntry> = repo.read_reflog(&refname)?;
+    let shown: &str = name.as_deref().unwrap_or("HEAD");
+
+    for (n, entry) in entries.iter().rev().enumerate() {
+        println!(
+            "{} {}@{{{}}}: {}",
+            entry.new[..8].yellow(),
+            shown,
+            n,
+            entry.message
+        );
+    }
+
+    Ok(())
+}
+
+fn expiry(repo: &Repository, expire: Option<String>) -> Result<Duration> {
+    let configured: Option<String> = match expire {
+        Some(value) => Some(value),
+        None => repo
+            .config()?
+            .get_str(EXPIRE_CONFIG_KEY)
+            .map(str::to_string),
+    };
+    match configured {
+        Some(value) => Ok(prune::parse_expiry(&value).ok_or(NubError::InvalidExpiry(value))?),
+        None => Ok(DEFAULT_EXPIRE),
+    }
+}
+
+fn expire(
+    repo: &Repository,
+    expire: Option<String>,
+    all: bool,
+    name: Option<String>,
+) -> Result<()> {
+    let age: Duration = expiry(repo, expire)?;
+    let cutoff: DateTime<Utc> = Utc::now() - chrono::Duration::from_std(age)?;
+
+    let refnames: Vec<String> = if all {
+        repo.list_reflogs()?
+    } else {
+        vec![repo.reflog_ref_name(name.as_deref().unwrap_or("HEAD"))?]
+    };
+
+    let mut expired: usize = 0;
+    let mut touched: usize = 0;
+    for refname in &refnames {
+        let entries: Vec<ReflogEntry> = repo.read_reflog(refname)?;
+        let kept: Vec<ReflogEntry> = entries
+            .iter()
+            .filter(|entry: &&ReflogEntry| entry.timestamp > cutoff)
+            .cloned()
+            .collect();
+        if kept.len() == entries.len() {
+            continue;
+        }
+        expired += entries.len() - kept.len();
+        touched += 1;
+        repo.write_reflog(refname, &kept)?;
+    }
+
+    println!(
+        "{} Expired {} entries from {} reflogs",
+        "✓".green().bold(),
+        expired.to_string().yellow(),
+        touched.to_string().yellow()
+    );
+
+    Ok(())
+}
+
+pub fn execute(action: Action) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+
+    match action {
+        Action::Show(name) => show(&repo, name),
+        Action::Expire {
+            expire: age,
+            all,
+            refname,
+        } => expire(&repo, age, all, refname),
+    }
+}
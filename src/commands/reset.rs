@@ -1,22 +1,308 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::objects::{Commit, short_hash};
 use crate::repository::Repository;
+use crate::worktree;
 use anyhow::Result;
 use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
 
-pub fn execute() -> Result<()> {
-    match Repository::find() {
-        Ok(repo) => {
-            let index_path: PathBuf = repo.index_path();
-            fs::write(&index_path, "[]")?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Soft,
+    Mixed,
+    Hard,
+}
+
+fn is_path(repo: &Repository, index: &Index, head: &HashMap<String, String>, arg: &str) -> bool {
+    let Ok(spec) = worktree::repo_relative(repo, arg) else {
+        return false;
+    };
+    repo.root.join(&spec).exists()
+        || head
+            .keys()
+            .chain(index.paths())
+            .any(|path: &String| worktree::matches_pathspec(path, &spec))
+}
+
+fn reset_paths(
+    repo: &Repository,
+    mut index: Index,
+    index_lock: LockFile,
+    source: Option<String>,
+    paths: Vec<String>,
+) -> Result<()> {
+    let head: HashMap<String, String> = repo.head_files()?;
+    let wanted: HashMap<String, String> = match &source {
+        Some(rev) => repo.commit_files(&repo.resolve_commit(rev)?)?,
+        None => head.clone(),
+    };
+
+    let mut targets: BTreeSet<String> = BTreeSet::new();
+    for arg in &paths {
+        let spec: String = worktree::repo_relative(repo, arg)?;
+        let matched: Vec<&String> = head
+            .keys()
+            .chain(wanted.keys())
+            .chain(index.paths())
+            .filter(|path: &&String| worktree::matches_pathspec(path, &spec))
+            .collect();
+        if matched.is_empty() {
+            return Err(NubError::FileNotFound(arg.clone()).into());
+        }
+        targets.extend(matched.into_iter().cloned());
+    }
+
+    let mut changed: Vec<&String> = Vec::new();
+    for path in &targets {
+        let before: Option<Option<String>> =
+            index.get(path).map(|hash: Option<&String>| hash.cloned());
+        index.reset_entry(path, wanted.get(path), head.get(path));
+        if index.get(path).map(|hash: Option<&String>| hash.cloned()) != before {
+            changed.push(path);
+        }
+    }
+    repo.write_locked_index(&index, index_lock)?;
+
+    if changed.is_empty() {
+        println!("{}", "Nothing to unstage".dimmed());
+    }
+    for path in changed {
+        println!("{} unstaged {}", "✓".green().bold(), path.cyan());
+    }
+
+    Ok(())
+}
 
-            println!("{} Reset successful.", "✓".green().bold());
+fn reset_commit(
+    repo: &Repository,
+    mut index: Index,
+    index_lock: LockFile,
+    mode: Mode,
+    rev: &str,
+) -> Result<()> {
+    let target_hash: String = repo.resolve_commit(rev)?;
+    let head_hash: Option<String> = repo.head_commit_hash()?;
+    let head: HashMap<String, String> = repo.head_files()?;
+    let target: HashMap<String, String> = repo.commit_files(&target_hash)?;
 
-            Ok(())
+    let mut staged: HashMap<String, String> = head.clone();
+    index.apply_to(&mut staged);
+
+    match mode {
+        Mode::Soft => {
+            let paths: BTreeSet<&String> = staged.keys().chain(target.keys()).collect();
+            let mut rebased: Index = index.clone();
+            rebased.clear();
+            for path in paths {
+                match (staged.get(path), target.get(path)) {
+                    (Some(hash), Some(wanted)) if hash == wanted => {}
+                    (Some(hash), _) => rebased.stage(path.clone(), hash.clone()),
+                    (None, _) => rebased.stage_deletion(path.clone()),
+                }
+            }
+            index = rebased;
+        }
+        Mode::Mixed => index.clear(),
+        Mode::Hard => {
+            for (path, hash) in &target {
+                if worktree::hash_file(repo, path).as_ref() != Some(hash) {
+                    let content: Vec<u8> = repo.read_blob(hash)?;
+                    worktree::write_file(repo, path, &content)?;
+                }
+            }
+            let tracked: BTreeSet<&String> = head.keys().chain(index.paths()).collect();
+            for path in tracked {
+                if !target.contains_key(path) {
+                    worktree::remove_file(repo, path)?;
+                }
+            }
+            index.clear();
+        }
+    }
+
+    repo.update_head(
+        &target_hash,
+        head_hash.as_deref(),
+        &format!("reset: moving to {}", rev),
+    )?;
+    repo.write_locked_index(&index, index_lock)?;
+
+    if mode != Mode::Soft {
+        for state_path in [repo.merge_head_path(), repo.merge_msg_path()] {
+            if state_path.exists() {
+                fs::remove_file(state_path)?;
+            }
         }
-        Err(e) => {
-            eprintln!("{} {}", "✗".red().bold(), e);
-            Err(e)
+    }
+
+    let commit: Commit = repo.read_commit(&target_hash)?;
+    println!(
+        "{} HEAD is now at {} {}",
+        "✓".green().bold(),
+        short_hash(&target_hash).yellow(),
+        commit.message.lines().next().unwrap_or("")
+    );
+
+    Ok(())
+}
+
+pub fn execute(mode: Option<Mode>, targets: Vec<String>, paths: Vec<String>) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+
+    if !paths.is_empty() {
+        if targets.len() > 1 {
+            return Err(NubError::AmbiguousRevision(targets.join(" ")).into());
+        }
+        return reset_paths(&repo, index, index_lock, targets.into_iter().next(), paths);
+    }
+
+    if targets.is_empty() && repo.head_commit_hash()?.is_none() {
+        if mode != Some(Mode::Soft) {
+            index.clear();
         }
+        repo.write_locked_index(&index, index_lock)?;
+        println!("{} Reset successful.", "✓".green().bold());
+        return Ok(());
+    }
+
+    let head: HashMap<String, String> = repo.head_files()?;
+    let commit_target: bool = match targets.as_slice() {
+        [] => true,
+        [rev] => {
+            mode.is_some()
+                || (repo.resolve_commit(rev).is_ok() && !is_path(&repo, &index, &head, rev))
+        }
+        _ => false,
+    };
+
+    if commit_target {
+        let rev: &str = targets.first().map(String::as_str).unwrap_or("HEAD");
+        reset_commit(&repo, index, index_lock, mode.unwrap_or(Mode::Mixed), rev)
+    } else if mode.is_some() {
+        Err(NubError::AmbiguousRevision(targets.join(" ")).into())
+    } else {
+        reset_paths(&repo, index, index_lock, None, targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::Author;
+    use tempfile::TempDir;
+
+    fn write_commit(repo: &Repository, files: &[(&str, &str)], parents: Vec<String>) -> String {
+        let mut tree: HashMap<String, String> = HashMap::new();
+        for (path, content) in files {
+            tree.insert(
+                path.to_string(),
+                repo.write_blob(content.as_bytes()).unwrap(),
+            );
+        }
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree_hash: String = repo.write_tree_from_files(&tree).unwrap();
+        let commit: Commit = Commit::new(tree_hash, parents, author, "commit".to_string());
+        repo.write_object(&Object::Commit(commit)).unwrap()
+    }
+
+    /// Builds `first` (a.txt = "a") and `second` (a.txt = "a2", b.txt = "b")
+    /// on main, with HEAD and the worktree at `second`.
+    fn two_commits(repo: &Repository) -> (String, String) {
+        let first: String = write_commit(repo, &[("a.txt", "a")], Vec::new());
+        let second: String = write_commit(
+            repo,
+            &[("a.txt", "a2"), ("b.txt", "b")],
+            vec![first.clone()],
+        );
+        repo.write_branch("main", &second, "commit").unwrap();
+        fs::write(repo.root.join("a.txt"), "a2").unwrap();
+        fs::write(repo.root.join("b.txt"), "b").unwrap();
+        (first, second)
+    }
+
+    fn reset(repo: &Repository, mode: Mode, rev: &str) {
+        let lock: LockFile = repo.lock_index().unwrap();
+        let index: Index = repo.read_index().unwrap();
+        reset_commit(repo, index, lock, mode, rev).unwrap();
+    }
+
+    #[test]
+    fn test_soft_reset_keeps_changes_staged() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let (first, second) = two_commits(&repo);
+
+        reset(&repo, Mode::Soft, &first);
+
+        assert_eq!(repo.head_commit_hash().unwrap(), Some(first));
+        let mut staged: HashMap<String, String> = repo.head_files().unwrap();
+        repo.read_index().unwrap().apply_to(&mut staged);
+        assert_eq!(staged, repo.commit_files(&second).unwrap());
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+    }
+
+    #[test]
+    fn test_mixed_reset_clears_index_and_keeps_worktree() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let (first, _) = two_commits(&repo);
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage_deletion("b.txt".to_string());
+        repo.write_index(&index).unwrap();
+
+        reset(&repo, Mode::Mixed, &first);
+
+        assert_eq!(repo.head_commit_hash().unwrap(), Some(first));
+        assert!(repo.read_index().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a2");
+        assert_eq!(fs::read_to_string(repo.root.join("b.txt")).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_hard_reset_rewrites_worktree() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let (first, _) = two_commits(&repo);
+        fs::write(repo.root.join("a.txt"), "local edit").unwrap();
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage("a.txt".to_string(), repo.write_blob(b"local edit").unwrap());
+        repo.write_index(&index).unwrap();
+
+        reset(&repo, Mode::Hard, &first);
+
+        assert_eq!(repo.head_commit_hash().unwrap(), Some(first));
+        assert!(repo.read_index().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(repo.root.join("a.txt")).unwrap(), "a");
+        assert!(!repo.root.join("b.txt").exists());
+    }
+
+    #[test]
+    fn test_path_reset_only_unstages_matching_paths() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let (_, second) = two_commits(&repo);
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage("a.txt".to_string(), repo.write_blob(b"a3").unwrap());
+        index.stage("c.txt".to_string(), repo.write_blob(b"c").unwrap());
+        repo.write_index(&index).unwrap();
+
+        let lock: LockFile = repo.lock_index().unwrap();
+        let index: Index = repo.read_index().unwrap();
+        let path: String = repo.root.join("a.txt").to_string_lossy().into_owned();
+        reset_paths(&repo, index, lock, None, vec![path]).unwrap();
+
+        let index: Index = repo.read_index().unwrap();
+        assert!(!index.contains("a.txt"));
+        assert!(index.contains("c.txt"));
+        assert_eq!(repo.head_commit_hash().unwrap(), Some(second));
     }
 }
@@ -0,0 +1,144 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+
+fn matching_paths(
+    repo: &Repository,
+    specs: &[String],
+    candidates: &BTreeSet<&String>,
+) -> Result<BTreeSet<String>> {
+    let mut matched: BTreeSet<String> = BTreeSet::new();
+    for arg in specs {
+        let spec: String = worktree::repo_relative(repo, arg)?;
+        let before: usize = matched.len();
+        for path in candidates {
+            if worktree::matches_pathspec(path, &spec) {
+                matched.insert(path.to_string());
+            }
+        }
+        if matched.len() == before {
+            return Err(NubError::PathspecNotMatched(arg.clone()).into());
+        }
+    }
+    Ok(matched)
+}
+
+fn restore(repo: &Repository, paths: &[String], source: Option<&str>, staged: bool) -> Result<()> {
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+
+    let committed: HashMap<String, String> = repo.head_files()?;
+    let mut tracked: HashMap<String, String> = committed.clone();
+    index.apply_to(&mut tracked);
+
+    let wanted: HashMap<String, String> = match source {
+        Some(rev) => repo.commit_files(&repo.resolve_commit(rev)?)?,
+        None if staged => committed.clone(),
+        None => tracked.clone(),
+    };
+
+    // Without --source the worktree is restored from the index, so only paths
+    // the index still knows about can match; anything else is left alone.
+    let candidates: BTreeSet<&String> = if staged {
+        wanted
+            .keys()
+            .chain(committed.keys())
+            .chain(index.paths())
+            .collect()
+    } else if source.is_some() {
+        wanted.keys().chain(tracked.keys()).collect()
+    } else {
+        tracked.keys().collect()
+    };
+    let targets: BTreeSet<String> = matching_paths(repo, paths, &candidates)?;
+
+    let mut restored: usize = 0;
+    if staged {
+        for path in &targets {
+            if tracked.get(path) == wanted.get(path) {
+                continue;
+            }
+            index.reset_entry(path, wanted.get(path), committed.get(path));
+            println!("{} unstaged {}", "✓".green().bold(), path.cyan());
+            restored += 1;
+        }
+    } else {
+        for path in &targets {
+            let working: Option<String> = worktree::hash_file(repo, path);
+            match wanted.get(path) {
+                Some(hash) if working.as_ref() != Some(hash) => {
+                    let content: Vec<u8> = repo.read_blob(hash)?;
+                    worktree::write_file(repo, path, &content)?;
+                    let action: &str = if working.is_some() {
+                        "overwrote"
+                    } else {
+                        "restored"
+                    };
+                    println!("{} {} {}", "✓".green().bold(), action, path.cyan());
+                    restored += 1;
+                }
+                None if working.is_some() && tracked.contains_key(path) => {
+                    worktree::remove_file(repo, path)?;
+                    println!("{} removed {}", "✓".green().bold(), path.cyan());
+                    restored += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    repo.write_locked_index(&index, index_lock)?;
+
+    if restored == 0 {
+        println!("{}", "Nothing to restore".dimmed());
+    }
+
+    Ok(())
+}
+
+pub fn execute(paths: Vec<String>, source: Option<String>, staged: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    restore(&repo, &paths, source.as_deref(), staged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::{Author, Commit};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restore_keeps_file_removed_from_index() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        fs::write(repo.root.join("k.txt"), "keep").unwrap();
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("k.txt".to_string(), repo.write_blob(b"keep").unwrap());
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree: String = repo.write_tree_from_files(&files).unwrap();
+        let commit: Commit = Commit::new(tree, Vec::new(), author, "commit".to_string());
+        let hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+        repo.write_branch("main", &hash, "commit").unwrap();
+
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage_deletion("k.txt".to_string());
+        repo.write_index(&index).unwrap();
+
+        let path: String = repo.root.join("k.txt").to_string_lossy().to_string();
+        assert!(restore(&repo, std::slice::from_ref(&path), None, false).is_err());
+        assert_eq!(fs::read_to_string(repo.root.join("k.txt")).unwrap(), "keep");
+
+        restore(&repo, &[path], None, true).unwrap();
+        assert!(repo.read_index().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(repo.root.join("k.txt")).unwrap(), "keep");
+    }
+}
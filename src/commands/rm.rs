@@ -0,0 +1,64 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+
+pub fn execute(paths: Vec<String>, cached: bool, force: bool) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+    let committed_tree: HashMap<String, String> = repo.head_files()?;
+
+    let mut tracked: HashMap<String, String> = committed_tree.clone();
+    index.apply_to(&mut tracked);
+
+    let mut targets: BTreeSet<String> = BTreeSet::new();
+    for arg in &paths {
+        let spec: String = worktree::repo_relative(&repo, arg)?;
+        let matched: Vec<&String> = tracked
+            .keys()
+            .filter(|path: &&String| worktree::matches_pathspec(path, &spec))
+            .collect();
+        if matched.is_empty() {
+            return Err(NubError::FileNotFound(arg.clone()).into());
+        }
+        targets.extend(matched.into_iter().cloned());
+    }
+
+    if !cached && !force {
+        let changed: Vec<String> = targets
+            .iter()
+            .filter(|path: &&String| {
+                let working: Option<String> = worktree::hash_file(&repo, path);
+                (working.is_some() && working.as_ref() != tracked.get(*path))
+                    || tracked.get(*path) != committed_tree.get(*path)
+            })
+            .cloned()
+            .collect();
+        if !changed.is_empty() {
+            return Err(NubError::UncommittedChanges(changed).into());
+        }
+    }
+
+    for path in &targets {
+        if committed_tree.contains_key(path) {
+            index.stage_deletion(path.clone());
+        } else {
+            index.unstage(path);
+        }
+
+        if !cached {
+            worktree::remove_file(&repo, path)?;
+        }
+
+        println!("{} removed {}", "✓".green().bold(), path.cyan());
+    }
+
+    repo.write_locked_index(&index, index_lock)?;
+
+    Ok(())
+}
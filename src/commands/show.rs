@@ -0,0 +1,225 @@
+use crate::commands::log;
+use crate::diff;
+use crate::error::NubError;
+use crate::objects::tree::TreeChange;
+use crate::objects::{Commit, ObjectType, Tag};
+use crate::repository::Repository;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+
+const STAT_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Patch,
+    Stat,
+    NameOnly,
+}
+
+/// Splits `rev:path` at the first colon that is not part of an `@{...}`
+/// reflog selector such as `main@{2024-01-01 12:00}`.
+fn split_rev_path(revision: &str) -> Option<(&str, &str)> {
+    let mut in_selector: bool = false;
+    for (i, c) in revision.char_indices() {
+        match c {
+            '{' if revision[..i].ends_with('@') => in_selector = true,
+            '}' => in_selector = false,
+            ':' if !in_selector => return Some((&revision[..i], &revision[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn show_path(repo: &Repository, rev: &str, path: &str) -> Result<()> {
+    let files: HashMap<String, String> = if rev.is_empty() {
+        let mut files: HashMap<String, String> = repo.head_files()?;
+        repo.read_index()?.apply_to(&mut files);
+        files
+    } else {
+        repo.commit_files(&repo.resolve_commit(rev)?)?
+    };
+    let path: &str = path.trim_matches('/');
+
+    if let Some(hash) = files.get(path) {
+        io::stdout().write_all(&repo.read_blob(hash)?)?;
+        return Ok(());
+    }
+
+    let prefix: String = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", path)
+    };
+    let children: BTreeSet<String> = files
+        .keys()
+        .filter_map(|file: &String| file.strip_prefix(&prefix))
+        .map(|rest: &str| match rest.split_once('/') {
+            Some((dir, _)) => format!("{}/", dir),
+            None => rest.to_string(),
+        })
+        .collect();
+    if children.is_empty() {
+        return Err(NubError::FileNotFound(format!("{}:{}", rev, path)).into());
+    }
+
+    println!("{} {}:{}", "tree".yellow(), rev, path);
+    println!();
+    for child in children {
+        println!("{}", child);
+    }
+
+    Ok(())
+}
+
+fn print_stat(repo: &Repository, changes: &[TreeChange]) -> Result<()> {
+    let mut rows: Vec<(&str, Option<(usize, usize)>)> = Vec::new();
+    for change in changes {
+        let old: Vec<u8> = match &change.old {
+            Some(hash) => repo.read_blob(hash)?,
+            None => Vec::new(),
+        };
+        let new: Vec<u8> = match &change.new {
+            Some(hash) => repo.read_blob(hash)?,
+            None => Vec::new(),
+        };
+        rows.push((&change.path, diff::line_counts(&old, &new)));
+    }
+
+    let name_width: usize = rows
+        .iter()
+        .map(|(path, _): &(&str, Option<(usize, usize)>)| path.len())
+        .max()
+        .unwrap_or(0);
+    let largest: usize = rows
+        .iter()
+        .filter_map(|(_, counts): &(&str, Option<(usize, usize)>)| *counts)
+        .map(|(added, removed): (usize, usize)| added + removed)
+        .max()
+        .unwrap_or(0);
+
+    let count_width: usize = largest.to_string().len();
+
+    let (mut insertions, mut deletions): (usize, usize) = (0, 0);
+    for (path, counts) in &rows {
+        let Some((added, removed)) = counts else {
+            println!(" {:<width$} | Bin", path, width = name_width);
+            continue;
+        };
+        insertions += added;
+        deletions += removed;
+        let scale = |n: usize| -> usize {
+            if largest <= STAT_WIDTH {
+                n
+            } else {
+                (n * STAT_WIDTH).div_ceil(largest)
+            }
+        };
+        println!(
+            " {:<width$} | {:>count_width$} {}{}",
+            path,
+            added + removed,
+            "+".repeat(scale(*added)).green(),
+            "-".repeat(scale(*removed)).red(),
+            width = name_width,
+            count_width = count_width
+        );
+    }
+    let plural = |n: usize| -> &str { if n == 1 { "" } else { "s" } };
+    println!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        rows.len(),
+        plural(rows.len()),
+        insertions,
+        plural(insertions),
+        deletions,
+        plural(deletions)
+    );
+
+    Ok(())
+}
+
+fn show_commit(repo: &Repository, hash: &str, format: Format) -> Result<()> {
+    let commit: Commit = repo.read_commit(hash)?;
+
+    println!("{}", log::commit_header(hash, &commit, true));
+
+    let parent_tree: Option<String> = match commit.parents.first() {
+        Some(parent) => Some(repo.read_commit(parent)?.tree),
+        None => None,
+    };
+    let changes: Vec<TreeChange> = repo.diff_trees(parent_tree.as_deref(), Some(&commit.tree))?;
+
+    match format {
+        Format::NameOnly => {
+            for change in &changes {
+                println!("{}", change.path);
+            }
+        }
+        Format::Stat => print_stat(repo, &changes)?,
+        Format::Patch => {
+            for change in &changes {
+                let old: Option<Vec<u8>> = match &change.old {
+                    Some(hash) => Some(repo.read_blob(hash)?),
+                    None => None,
+                };
+                let new: Option<Vec<u8>> = match &change.new {
+                    Some(hash) => Some(repo.read_blob(hash)?),
+                    None => None,
+                };
+                diff::print_file_diff(&change.path, old.as_deref(), new.as_deref());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute(revision: Option<String>, format: Format) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+    let revision: String = revision.unwrap_or_else(|| "HEAD".to_string());
+
+    if let Some((rev, path)) = split_rev_path(&revision) {
+        return show_path(&repo, rev, path);
+    }
+
+    let mut hash: String = match repo.resolve_object(&revision) {
+        Ok(hash) => hash,
+        Err(_) => repo.resolve_commit(&revision)?,
+    };
+    while repo.read_object(&hash)?.0 == ObjectType::Tag {
+        let tag: Tag = repo.read_tag(&hash)?;
+        println!("{} {}", "tag".yellow(), tag.tag);
+        println!("Tagger: {} <{}>", tag.tagger.name, tag.tagger.email);
+        println!("Date:   {}", log::format_date(&tag.timestamp));
+        println!();
+        for line in tag.message.lines() {
+            println!("    {}", line);
+        }
+        println!();
+        hash = tag.object;
+    }
+
+    show_commit(&repo, &repo.peel_to_commit(&hash)?, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_rev_path_skips_reflog_selectors() {
+        assert_eq!(
+            split_rev_path("HEAD:src/main.rs"),
+            Some(("HEAD", "src/main.rs"))
+        );
+        assert_eq!(split_rev_path(":README"), Some(("", "README")));
+        assert_eq!(
+            split_rev_path("main@{2024-01-01 12:00:00}:a.txt"),
+            Some(("main@{2024-01-01 12:00:00}", "a.txt"))
+        );
+        assert_eq!(split_rev_path("main@{2024-01-01 12:00:00}"), None);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::commands::merge::{self, Resolution};
+use crate::diff;
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::objects::commit::{Author, Commit};
+use crate::objects::tree::TreeChange;
+use crate::objects::{Object, short_hash};
+use crate::reflog::ReflogEntry;
+use crate::repository::Repository;
+use crate::worktree;
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+
+const STASH_REF: &str = "refs/stash";
+
+pub enum Action {
+    Push(Option<String>),
+    List,
+    Show(Option<String>, bool),
+    Apply(Option<String>),
+    Pop(Option<String>),
+    Drop(Option<String>),
+}
+
+fn parse_stash_index(stash: Option<&str>) -> Result<usize> {
+    let Some(stash) = stash else {
+        return Ok(0);
+    };
+    let number: &str = stash
+        .strip_prefix("stash@{")
+        .and_then(|rest: &str| rest.strip_suffix('}'))
+        .unwrap_or(stash);
+    Ok(number
+        .parse()
+        .map_err(|_| NubError::StashNotFound(stash.to_string()))?)
+}
+
+fn stash_entry(repo: &Repository, stash: Option<&str>) -> Result<(usize, ReflogEntry)> {
+    let n: usize = parse_stash_index(stash)?;
+    let entries: Vec<ReflogEntry> = repo.read_reflog(STASH_REF)?;
+    if n >= entries.len() {
+        return Err(NubError::StashNotFound(format!("stash@{{{}}}", n)).into());
+    }
+    Ok((n, entries[entries.len() - 1 - n].clone()))
+}
+
+fn head_summary(repo: &Repository, head: &str) -> Result<(String, String)> {
+    let branch: String = repo
+        .current_branch()?
+        .unwrap_or_else(|| "(no branch)".to_string());
+    let commit: Commit = repo.read_commit(head)?;
+    let subject: &str = commit.message.lines().next().unwrap_or("");
+    Ok((branch, format!("{} {}", short_hash(head), subject)))
+}
+
+fn push(repo: &Repository, message: Option<String>) -> Result<()> {
+    let head: String = repo
+        .head_commit_hash()?
+        .ok_or_else(|| NubError::RevisionNotFound("HEAD".to_string()))?;
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+
+    let committed: HashMap<String, String> = repo.commit_files(&head)?;
+    let mut staged: HashMap<String, String> = committed.clone();
+    index.apply_to(&mut staged);
+
+    let mut working: HashMap<String, String> = HashMap::new();
+    for (path, hash) in &staged {
+        let Some(current) = worktree::hash_file(repo, path) else {
+            continue;
+        };
+        if &current != hash {
+            repo.write_blob(&fs::read(repo.root.join(path))?)?;
+        }
+        working.insert(path.clone(), current);
+    }
+
+    if index.is_empty() && working == committed {
+        println!("{}", "No local changes to save".dimmed());
+        return Ok(());
+    }
+
+    let (branch, summary) = head_summary(repo, &head)?;
+    let message: String = match message {
+        Some(text) => format!("On {}: {}", branch, text),
+        None => format!("WIP on {}: {}", branch, summary),
+    };
+    let author: Author = repo.config()?.identity();
+
+    let index_commit: Commit = Commit::new(
+        repo.write_tree_from_files(&staged)?,
+        vec![head.clone()],
+        author.clone(),
+        format!("index on {}: {}", branch, summary),
+    );
+    let index_hash: String = repo.write_object(&Object::Commit(index_commit))?;
+    let stash_commit: Commit = Commit::new(
+        repo.write_tree_from_files(&working)?,
+        vec![head.clone(), index_hash],
+        author,
+        message.clone(),
+    );
+    let stash_hash: String = repo.write_object(&Object::Commit(stash_commit))?;
+
+    let previous: Option<String> = repo.read_ref(STASH_REF)?;
+    repo.update_ref(STASH_REF, &stash_hash, previous.as_deref(), &message)?;
+
+    worktree::switch_files(repo, &working, &committed)?;
+    for path in staged.keys() {
+        if !committed.contains_key(path) && !working.contains_key(path) {
+            worktree::remove_file(repo, path)?;
+        }
+    }
+    index.clear();
+    repo.write_locked_index(&index, index_lock)?;
+
+    println!(
+        "{} Saved working directory and index state {}",
+        "✓".green().bold(),
+        message
+    );
+
+    Ok(())
+}
+
+fn list(repo: &Repository) -> Result<()> {
+    for (n, entry) in repo.read_reflog(STASH_REF)?.iter().rev().enumerate() {
+        println!("{}: {}", format!("stash@{{{}}}", n).yellow(), entry.message);
+    }
+    Ok(())
+}
+
+fn show(repo: &Repository, stash: Option<&str>, patch: bool) -> Result<()> {
+    let (_, entry) = stash_entry(repo, stash)?;
+    let commit: Commit = repo.read_commit(&entry.new)?;
+    let base_tree: String = repo.read_commit(&commit.parents[0])?.tree;
+
+    let changes: Vec<TreeChange> = repo.diff_trees(Some(&base_tree), Some(&commit.tree))?;
+    for change in &changes {
+        if patch {
+            let old: Option<Vec<u8>> = match &change.old {
+                Some(hash) => Some(repo.read_blob(hash)?),
+                None => None,
+            };
+            let new: Option<Vec<u8>> = match &change.new {
+                Some(hash) => Some(repo.read_blob(hash)?),
+                None => None,
+            };
+            diff::print_file_diff(&change.path, old.as_deref(), new.as_deref());
+            continue;
+        }
+        let status: colored::ColoredString = match (&change.old, &change.new) {
+            (None, _) => "A".green(),
+            (_, None) => "D".red(),
+            _ => "M".yellow(),
+        };
+        println!("  {}\t{}", status, change.path);
+    }
+
+    Ok(())
+}
+
+fn apply(repo: &Repository, stash: Option<&str>) -> Result<bool> {
+    let (n, entry) = stash_entry(repo, stash)?;
+    let label: String = format!("stash@{{{}}}", n);
+    let commit: Commit = repo.read_commit(&entry.new)?;
+    let base: &String = &commit.parents[0];
+    let base_files: HashMap<String, String> = repo.commit_files(base)?;
+    let stash_files: HashMap<String, String> = repo.tree_files(&commit.tree)?;
+    let index_files: Option<HashMap<String, String>> = match commit.parents.get(1) {
+        Some(hash) => Some(repo.commit_files(hash)?),
+        None => None,
+    };
+
+    let head: Option<String> = repo.head_commit_hash()?;
+    let index_lock: LockFile = repo.lock_index()?;
+    let mut index: Index = repo.read_index()?;
+    let committed: HashMap<String, String> = repo.head_files()?;
+    let mut ours: HashMap<String, String> = committed.clone();
+    index.apply_to(&mut ours);
+
+    let paths: BTreeSet<&String> = base_files
+        .keys()
+        .chain(stash_files.keys())
+        .filter(|path: &&String| base_files.get(*path) != stash_files.get(*path))
+        .collect();
+
+    let dirty: Vec<String> = paths
+        .iter()
+        .filter(|path: &&&String| ours.get(**path) != stash_files.get(**path))
+        .filter(|path: &&&String| worktree::hash_file(repo, path).as_ref() != ours.get(**path))
+        .map(|path: &&String| path.to_string())
+        .collect();
+    if !dirty.is_empty() {
+        return Err(NubError::WouldOverwrite(dirty).into());
+    }
+
+    let restore_index: bool = head.as_ref() == Some(base) && index.is_empty();
+    let mut conflicts: Vec<(String, &'static str)> = Vec::new();
+    for path in paths {
+        let resolution: Resolution = merge::resolve_path(
+            repo,
+            path,
+            base_files.get(path),
+            ours.get(path),
+            stash_files.get(path),
+            &label,
+        )?;
+        match resolution {
+            Resolution::Keep => {}
+            Resolution::Take(hash) => {
+                let content: Vec<u8> = repo.read_blob(&hash)?;
+                worktree::write_file(repo, path, &content)?;
+                if !ours.contains_key(path) {
+                    index.stage(path.clone(), hash);
+                }
+            }
+            Resolution::Delete => worktree::remove_file(repo, path)?,
+            Resolution::Conflict(kind) => conflicts.push((path.clone(), kind)),
+        }
+    }
+
+    if restore_index
+        && conflicts.is_empty()
+        && let Some(index_files) = &index_files
+    {
+        index.clear();
+        let paths: BTreeSet<&String> = index_files.keys().chain(committed.keys()).collect();
+        for path in paths {
+            index.reset_entry(path, index_files.get(path), committed.get(path));
+        }
+    }
+    repo.write_locked_index(&index, index_lock)?;
+
+    for (path, kind) in &conflicts {
+        println!(
+            "{} Merge conflict ({}) in {}",
+            "✗".red().bold(),
+            kind,
+            path.red()
+        );
+    }
+    if conflicts.is_empty() {
+        println!("{} Applied {}", "✓".green().bold(), label.yellow());
+    }
+
+    Ok(conflicts.is_empty())
+}
+
+fn drop_entry(repo: &Repository, stash: Option<&str>) -> Result<()> {
+    let n: usize = parse_stash_index(stash)?;
+    let entry: ReflogEntry = repo
+        .drop_reflog_entry(STASH_REF, n)?
+        .ok_or_else(|| NubError::StashNotFound(format!("stash@{{{}}}", n)))?;
+
+    println!(
+        "{} Dropped stash@{{{}}} ({})",
+        "✓".green().bold(),
+        n,
+        short_hash(&entry.new).yellow()
+    );
+
+    Ok(())
+}
+
+fn pop(repo: &Repository, stash: Option<&str>) -> Result<()> {
+    if apply(repo, stash)? {
+        drop_entry(repo, stash)
+    } else {
+        println!("The stash entry is kept in case you need it again");
+        Ok(())
+    }
+}
+
+pub fn execute(action: Action) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+
+    match action {
+        Action::Push(message) => push(&repo, message),
+        Action::List => list(&repo),
+        Action::Show(stash, patch) => show(&repo, stash.as_deref(), patch),
+        Action::Apply(stash) => apply(&repo, stash.as_deref()).map(|_| ()),
+        Action::Pop(stash) => pop(&repo, stash.as_deref()),
+        Action::Drop(stash) => drop_entry(&repo, stash.as_deref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_commit(repo: &Repository, files: &[(&str, &str)], parents: Vec<String>) -> String {
+        let mut tree: HashMap<String, String> = HashMap::new();
+        for (path, content) in files {
+            worktree::write_file(repo, path, content.as_bytes()).unwrap();
+            tree.insert(
+                path.to_string(),
+                repo.write_blob(content.as_bytes()).unwrap(),
+            );
+        }
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree_hash: String = repo.write_tree_from_files(&tree).unwrap();
+        let commit: Commit = Commit::new(tree_hash, parents, author, "commit".to_string());
+        let hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+        repo.write_branch("main", &hash, "commit").unwrap();
+        hash
+    }
+
+    fn read(repo: &Repository, path: &str) -> String {
+        fs::read_to_string(repo.root.join(path)).unwrap()
+    }
+
+    #[test]
+    fn test_push_and_pop_round_trip() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let head: String = write_commit(&repo, &[("a.txt", "a"), ("b.txt", "b")], Vec::new());
+        fs::write(repo.root.join("a.txt"), "a2").unwrap();
+        fs::write(repo.root.join("b.txt"), "b2").unwrap();
+        let staged: String = repo.write_blob(b"b2").unwrap();
+        let mut index: Index = repo.read_index().unwrap();
+        index.stage("b.txt".to_string(), staged.clone());
+        repo.write_index(&index).unwrap();
+
+        push(&repo, None).unwrap();
+
+        assert_eq!(repo.head_commit_hash().unwrap(), Some(head));
+        assert_eq!(read(&repo, "a.txt"), "a");
+        assert_eq!(read(&repo, "b.txt"), "b");
+        assert!(repo.read_index().unwrap().is_empty());
+        assert_eq!(repo.read_reflog(STASH_REF).unwrap().len(), 1);
+
+        pop(&repo, None).unwrap();
+
+        assert_eq!(read(&repo, "a.txt"), "a2");
+        assert_eq!(read(&repo, "b.txt"), "b2");
+        let index: Index = repo.read_index().unwrap();
+        assert_eq!(index.get("b.txt"), Some(Some(&staged)));
+        assert!(!index.contains("a.txt"));
+        assert_eq!(repo.read_ref(STASH_REF).unwrap(), None);
+        assert!(repo.read_reflog(STASH_REF).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_pop_keeps_entry() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let base: String = write_commit(&repo, &[("a.txt", "a")], Vec::new());
+        fs::write(repo.root.join("a.txt"), "stashed").unwrap();
+        push(&repo, None).unwrap();
+        let stashed: Option<String> = repo.read_ref(STASH_REF).unwrap();
+        write_commit(&repo, &[("a.txt", "committed")], vec![base]);
+
+        pop(&repo, None).unwrap();
+
+        assert!(read(&repo, "a.txt").contains("<<<<<<<"));
+        assert_eq!(repo.read_ref(STASH_REF).unwrap(), stashed);
+        assert_eq!(repo.read_reflog(STASH_REF).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_drop_relinks_remaining_entries() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        write_commit(&repo, &[("a.txt", "a")], Vec::new());
+        for content in ["one", "two"] {
+            fs::write(repo.root.join("a.txt"), content).unwrap();
+            push(&repo, Some(content.to_string())).unwrap();
+        }
+        let top: Option<String> = repo.read_ref(STASH_REF).unwrap();
+
+        drop_entry(&repo, Some("stash@{1}")).unwrap();
+
+        let entries: Vec<ReflogEntry> = repo.read_reflog(STASH_REF).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].old_hash(), None);
+        assert_eq!(Some(entries[0].new.clone()), top);
+        assert_eq!(repo.read_ref(STASH_REF).unwrap(), top);
+
+        fs::write(repo.nub_dir.join(STASH_REF), "0".repeat(64)).unwrap();
+        let err: anyhow::Error = drop_entry(&repo, None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::RefChanged(_))
+        ));
+        assert_eq!(repo.read_reflog(STASH_REF).unwrap().len(), 1);
+    }
+}
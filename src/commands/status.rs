@@ -1,139 +1,107 @@
-use crate::repository::Repository;
+use crate::ignore::IgnoreRules;
+use crate::index::Index;
+use crate::lockfile::LockFile;
+use crate::repository::{Head, Repository};
+use crate::worktree;
 use anyhow::Result;
 use colored::Colorize;
-use serde_json::Value;
-use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
-use std::fs;
 
 pub fn execute() -> Result<()> {
     let repo = Repository::find()?;
-    let index_path = repo.index_path();
-    let head_path = repo.head_path();
-
-    let index_data = fs::read_to_string(&index_path).unwrap_or_else(|_| "[]".to_string());
-    let index: Vec<Value> = serde_json::from_str(&index_data).unwrap_or_default();
-
-    let index_map: HashMap<String, String> = index
-        .iter()
-        .filter_map(|v| {
-            Some((
-                v.get("path")?.as_str()?.replace("\\", "/"),
-                v.get("hash")?.as_str()?.to_string(),
-            ))
-        })
-        .collect();
-
-    let head_ref = fs::read_to_string(&head_path)?;
-    let head_ref_path = head_ref.trim_start_matches("ref: ").trim();
-
-    let branch_path = repo.nub_dir.join(head_ref_path);
-
-    let last_commit_hash = if branch_path.exists() {
-        Some(fs::read_to_string(&branch_path)?.trim().to_string())
-    } else {
-        None
-    };
-
-    let committed_tree: HashMap<String, String> = if let Some(commit_hash) = &last_commit_hash {
-        let commit_path = repo.commits_dir().join(commit_hash);
-        if commit_path.exists() {
-            let commit_data = fs::read_to_string(commit_path)?;
-            let commit_json: Value = serde_json::from_str(&commit_data)?;
-
-            if let Some(tree_hash) = commit_json.get("tree").and_then(|v| v.as_str()) {
-                let tree_path = repo.objects_dir().join(tree_hash);
-                if tree_path.exists() {
-                    let tree_data = fs::read_to_string(tree_path)?;
-                    let tree_json: Value = serde_json::from_str(&tree_data)?;
-
-                    tree_json["entries"]
-                        .as_array()
-                        .unwrap_or(&vec![])
-                        .iter()
-                        .filter_map(|e| {
-                            Some((
-                                e.get("name")?.as_str()?.replace("\\", "/"),
-                                e.get("hash")?.as_str()?.to_string(),
-                            ))
-                        })
-                        .collect()
-                } else {
-                    HashMap::new()
-                }
-            } else {
-                HashMap::new()
-            }
-        } else {
-            HashMap::new()
-        }
-    } else {
-        HashMap::new()
-    };
+    let index_lock: Option<LockFile> = repo.lock_index().ok();
+    let mut index: Index = repo.read_index()?;
+
+    let head = repo.read_head()?;
+    let committed_tree: HashMap<String, String> = repo.head_files()?;
 
     let mut all_paths: HashSet<String> = HashSet::new();
-    all_paths.extend(index_map.keys().cloned());
+    all_paths.extend(index.paths().cloned());
     all_paths.extend(committed_tree.keys().cloned());
 
     let mut working_files: HashMap<String, String> = HashMap::new();
+    let mut untracked: Vec<String> = vec![];
 
+    let cached_index: Index = index.clone();
     for path_str in &all_paths {
-        let full_path = repo.root.join(path_str);
-        if full_path.exists() && full_path.is_file() {
-            if let Ok(data) = fs::read(&full_path) {
-                let mut hasher = Sha256::new();
-                hasher.update(&data);
-                let hash = format!("{:x}", hasher.finalize());
-                working_files.insert(path_str.clone(), hash);
-            }
+        if let Some(hash) = worktree::hash_file_cached(&repo, &mut index, path_str) {
+            working_files.insert(path_str.clone(), hash);
         }
     }
+    index.retain_stats(|path: &String| all_paths.contains(path));
+    if let Some(lock) = index_lock
+        && index != cached_index
+    {
+        repo.write_locked_index(&index, lock)?;
+    }
+
+    let mut ignore: IgnoreRules = repo.ignore_rules();
 
     for entry in walkdir::WalkDir::new(&repo.root)
         .into_iter()
         .filter_entry(|e| {
-            !e.path()
-                .strip_prefix(&repo.root)
-                .unwrap()
-                .starts_with(".nub-vcs")
+            let rel = e.path().strip_prefix(&repo.root).unwrap();
+            if rel.starts_with(".nub-vcs") {
+                return false;
+            }
+            if e.depth() == 0 {
+                return true;
+            }
+
+            let rel_str = rel.to_string_lossy().replace("\\", "/");
+            let is_dir = e.file_type().is_dir();
+            if ignore.is_ignored(&rel_str, is_dir) {
+                return false;
+            }
+            if is_dir {
+                ignore.load_dir(&rel_str);
+            }
+            true
         })
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
     {
-        let path = entry.path();
-        let rel = path.strip_prefix(&repo.root).unwrap();
+        let rel = entry.path().strip_prefix(&repo.root).unwrap();
         let rel_str = rel.to_string_lossy().replace("\\", "/");
 
         if !all_paths.contains(&rel_str) {
-            if let Ok(data) = fs::read(path) {
-                let mut hasher = Sha256::new();
-                hasher.update(&data);
-                let hash = format!("{:x}", hasher.finalize());
-                working_files.insert(rel_str, hash);
-            }
+            untracked.push(rel_str);
         }
     }
 
     let mut staged: Vec<String> = vec![];
+    let mut staged_deleted: Vec<String> = vec![];
     let mut modified: Vec<String> = vec![];
-    let mut untracked: Vec<String> = vec![];
+    let mut deleted: Vec<String> = vec![];
 
-    for (path, index_hash) in &index_map {
-        match committed_tree.get(path) {
-            Some(commit_hash) if commit_hash != index_hash => {
-                staged.push(path.clone());
-            }
-            None => {
-                staged.push(path.clone());
-            }
-            _ => {}
+    for (path, index_hash) in index.entries() {
+        let commit_hash = committed_tree.get(path);
+        if commit_hash == index_hash {
+            continue;
+        }
+        match index_hash {
+            Some(_) => staged.push(path.clone()),
+            None if commit_hash.is_some() => staged_deleted.push(path.clone()),
+            None => {}
+        }
+    }
+
+    for path in &all_paths {
+        let tracked = match index.get(path) {
+            Some(index_hash) => index_hash.is_some(),
+            None => committed_tree.contains_key(path),
+        };
+        if tracked && !working_files.contains_key(path) {
+            deleted.push(path.clone());
         }
     }
 
     for (path, work_hash) in &working_files {
-        if let Some(index_hash) = index_map.get(path) {
-            if index_hash != work_hash {
-                modified.push(path.clone());
+        if let Some(index_hash) = index.get(path) {
+            match index_hash {
+                Some(index_hash) if index_hash != work_hash => modified.push(path.clone()),
+                Some(_) => {}
+                None => untracked.push(path.clone()),
             }
         } else {
             match committed_tree.get(path) {
@@ -149,18 +117,32 @@ pub fn execute() -> Result<()> {
         }
     }
 
-    println!("{}", "On branch:".bold());
-    println!(
-        " {}",
-        head_ref_path.split('/').last().unwrap_or("unknown").cyan()
-    );
+    match &head {
+        Head::Branch(name) => {
+            println!("{}", "On branch:".bold());
+            println!(" {}", name.cyan());
+        }
+        Head::Detached(hash) => {
+            println!("{}", "HEAD detached at:".bold());
+            println!(" {}", hash[..8.min(hash.len())].cyan());
+        }
+    }
     println!();
 
-    if !staged.is_empty() {
+    staged.sort();
+    staged_deleted.sort();
+    modified.sort();
+    deleted.sort();
+    untracked.sort();
+
+    if !staged.is_empty() || !staged_deleted.is_empty() {
         println!("{}", "Staged for commit:".green().bold());
         for file in &staged {
             println!("  {}", file.green());
         }
+        for file in &staged_deleted {
+            println!("  {} {}", "deleted:".green(), file.green());
+        }
         println!();
     }
 
@@ -172,6 +154,14 @@ pub fn execute() -> Result<()> {
         println!();
     }
 
+    if !deleted.is_empty() {
+        println!("{}", "Deleted:".yellow().bold());
+        for file in &deleted {
+            println!("  {}", file.yellow());
+        }
+        println!();
+    }
+
     if !untracked.is_empty() {
         println!("{}", "Untracked files:".red().bold());
         for file in &untracked {
@@ -180,7 +170,12 @@ pub fn execute() -> Result<()> {
         println!();
     }
 
-    if staged.is_empty() && modified.is_empty() && untracked.is_empty() {
+    if staged.is_empty()
+        && staged_deleted.is_empty()
+        && modified.is_empty()
+        && deleted.is_empty()
+        && untracked.is_empty()
+    {
         println!("{}", "✓ Working directory clean".green().bold());
     }
 
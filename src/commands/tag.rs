@@ -0,0 +1,97 @@
+use crate::error::NubError;
+use crate::ignore;
+use crate::objects::{Object, ObjectType, Tag};
+use crate::repository::{self, Repository};
+use anyhow::Result;
+use colored::Colorize;
+
+fn list(repo: &Repository, pattern: Option<&str>) -> Result<()> {
+    let pattern: Option<Vec<char>> = pattern.map(|p: &str| p.chars().collect());
+    for name in repo.list_tags()? {
+        let text: Vec<char> = name.chars().collect();
+        if pattern
+            .as_ref()
+            .is_none_or(|p: &Vec<char>| ignore::glob_match(p, &text))
+        {
+            println!("{}", name);
+        }
+    }
+    Ok(())
+}
+
+fn create(
+    repo: &Repository,
+    name: &str,
+    target: Option<String>,
+    message: Option<String>,
+    force: bool,
+) -> Result<()> {
+    if !repository::is_valid_ref_name(name) {
+        return Err(NubError::InvalidTagName(name.to_string()).into());
+    }
+    if !force && repo.read_tag_ref(name)?.is_some() {
+        return Err(NubError::TagAlreadyExists(name.to_string()).into());
+    }
+
+    let commit_hash: String = repo.resolve_commit(target.as_deref().unwrap_or("HEAD"))?;
+
+    let (kind, hash): (&str, String) = match message {
+        Some(message) => {
+            let tag: Tag = Tag::new(
+                commit_hash.clone(),
+                ObjectType::Commit.to_string(),
+                name.to_string(),
+                repo.config()?.identity(),
+                message,
+            );
+            ("annotated tag", repo.write_object(&Object::Tag(tag))?)
+        }
+        None => ("tag", commit_hash.clone()),
+    };
+    repo.write_tag_ref(name, &hash)?;
+
+    println!(
+        "{} Created {} {} at {}",
+        "✓".green().bold(),
+        kind,
+        name.cyan(),
+        commit_hash[..8].yellow()
+    );
+    Ok(())
+}
+
+fn delete(repo: &Repository, name: &str) -> Result<()> {
+    let hash: String = repo
+        .read_tag_ref(name)?
+        .ok_or_else(|| NubError::TagNotFound(name.to_string()))?;
+    repo.delete_tag(name)?;
+
+    println!(
+        "{} Deleted tag {} (was {})",
+        "✓".green().bold(),
+        name.cyan(),
+        hash[..8].yellow()
+    );
+    Ok(())
+}
+
+pub fn execute(
+    name: Option<String>,
+    target: Option<String>,
+    message: Option<String>,
+    list_tags: bool,
+    delete_tag: bool,
+    force: bool,
+) -> Result<()> {
+    let repo: Repository = Repository::find()?;
+
+    if delete_tag {
+        let name: String = name.ok_or_else(|| NubError::TagNotFound(String::new()))?;
+        return delete(&repo, &name);
+    }
+
+    match name {
+        Some(name) if !list_tags => create(&repo, &name, target, message, force),
+        pattern => list(&repo, pattern.as_deref()),
+    }
+}
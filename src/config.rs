@@ -0,0 +1,374 @@
+use crate::error::NubError;
+use crate::lockfile;
+use crate::objects::commit::Author;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const ENV_PREFIX: &str = "NUB_CONFIG_";
+const SYSTEM_CONFIG_ENV: &str = "NUB_SYSTEM_CONFIG";
+const GLOBAL_CONFIG_ENV: &str = "NUB_GLOBAL_CONFIG";
+const SYSTEM_CONFIG_PATH: &str = "/etc/nubconfig";
+const GLOBAL_CONFIG_FILE: &str = ".nubconfig";
+const DEFAULT_USER_NAME: &str = "NUB User";
+const DEFAULT_USER_EMAIL: &str = "user@nub.local";
+
+/// Keys that `NUB_CONFIG_<SECTION>_<NAME>` can override. An environment
+/// variable name cannot show where the section ends, so each is listed.
+const ENV_KEYS: &[&str] = &[
+    "user.name",
+    "user.email",
+    "color.ui",
+    "gc.pruneExpire",
+    "gc.reflogExpire",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    System,
+    Global,
+    Local,
+}
+
+impl Level {
+    pub fn path(&self, local: Option<&Path>) -> Option<PathBuf> {
+        match self {
+            Level::System => Some(
+                env::var_os(SYSTEM_CONFIG_ENV)
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| PathBuf::from(SYSTEM_CONFIG_PATH)),
+            ),
+            Level::Global => env::var_os(GLOBAL_CONFIG_ENV)
+                .map(PathBuf::from)
+                .or_else(|| {
+                    env::var_os("HOME").map(|home| PathBuf::from(home).join(GLOBAL_CONFIG_FILE))
+                }),
+            Level::Local => local.map(Path::to_path_buf),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(Level, PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::File(_, path) => write!(f, "file:{}", path.display()),
+            Origin::Env(name) => write!(f, "env:{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigValue {
+    pub value: String,
+    pub origin: Origin,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    values: BTreeMap<String, ConfigValue>,
+}
+
+pub fn normalize_key(key: &str) -> Result<String> {
+    let valid: bool = key
+        .split_once('.')
+        .is_some_and(|(section, name): (&str, &str)| {
+            !section.is_empty()
+                && !name.is_empty()
+                && key.split('.').all(|part: &str| {
+                    !part.is_empty()
+                        && part
+                            .chars()
+                            .all(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                })
+        });
+    if !valid {
+        return Err(NubError::InvalidConfigKey(key.to_string()).into());
+    }
+    Ok(key.to_ascii_lowercase())
+}
+
+fn env_key(name: &str) -> Option<&'static str> {
+    let rest: &str = name.strip_prefix(ENV_PREFIX)?;
+    ENV_KEYS
+        .iter()
+        .copied()
+        .find(|key: &&str| key.replace('.', "_").eq_ignore_ascii_case(rest))
+}
+
+fn read_file(path: &Path) -> Result<Map<String, Value>> {
+    let data: String = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(err) => return Err(err.into()),
+    };
+    if data.trim().is_empty() {
+        return Ok(Map::new());
+    }
+    match serde_json::from_str(&data) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(NubError::CorruptConfig(
+            path.display().to_string(),
+            "not an object".to_string(),
+        )
+        .into()),
+        Err(err) => {
+            Err(NubError::CorruptConfig(path.display().to_string(), err.to_string()).into())
+        }
+    }
+}
+
+fn flatten(prefix: &str, map: &Map<String, Value>, out: &mut Vec<(String, String)>) {
+    for (name, value) in map {
+        let key: String = if prefix.is_empty() {
+            name.to_ascii_lowercase()
+        } else {
+            format!("{}.{}", prefix, name.to_ascii_lowercase())
+        };
+        match value {
+            Value::Object(inner) => flatten(&key, inner, out),
+            Value::String(text) => out.push((key, text.clone())),
+            Value::Null => {}
+            other => out.push((key, other.to_string())),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(local: Option<&Path>) -> Result<Config> {
+        let mut files: Vec<(Level, PathBuf)> = Vec::new();
+        for level in [Level::System, Level::Global, Level::Local] {
+            if let Some(path) = level.path(local) {
+                files.push((level, path));
+            }
+        }
+        Config::load_from(&files, env::vars())
+    }
+
+    pub fn load_from(
+        files: &[(Level, PathBuf)],
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Config> {
+        let mut config: Config = Config::default();
+        for (level, path) in files {
+            let mut entries: Vec<(String, String)> = Vec::new();
+            flatten("", &read_file(path)?, &mut entries);
+            for (key, value) in entries {
+                let origin: Origin = Origin::File(*level, path.clone());
+                config.values.insert(key, ConfigValue { value, origin });
+            }
+        }
+
+        for (name, value) in vars {
+            let Some(key) = env_key(&name) else {
+                continue;
+            };
+            let key: String = key.to_ascii_lowercase();
+            let origin: Origin = Origin::Env(name.clone());
+            config.values.insert(key, ConfigValue { value, origin });
+        }
+        Ok(config)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        self.values.get(&key.to_ascii_lowercase())
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).map(|v: &ConfigValue| v.value.as_str())
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        let Some(value) = self.get_str(key) else {
+            return Ok(None);
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Some(true)),
+            "false" | "no" | "off" | "0" => Ok(Some(false)),
+            _ => Err(NubError::InvalidConfigValue(key.to_string(), value.to_string()).into()),
+        }
+    }
+
+    /// Falls back to the placeholder identity older repositories were created
+    /// with, so committing works before `user.name`/`user.email` are set.
+    pub fn identity(&self) -> Author {
+        Author {
+            name: self
+                .get_str("user.name")
+                .unwrap_or(DEFAULT_USER_NAME)
+                .to_string(),
+            email: self
+                .get_str("user.email")
+                .unwrap_or(DEFAULT_USER_EMAIL)
+                .to_string(),
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &ConfigValue)> {
+        self.values.iter()
+    }
+}
+
+pub fn set_value(path: &Path, key: &str, value: &str) -> Result<()> {
+    let key: String = normalize_key(key)?;
+    let mut root: Map<String, Value> = read_file(path)?;
+    insert_key(&mut root, &key.split('.').collect::<Vec<&str>>(), value);
+    write_file(path, root)
+}
+
+pub fn unset_value(path: &Path, key: &str) -> Result<()> {
+    let key: String = normalize_key(key)?;
+    let mut root: Map<String, Value> = read_file(path)?;
+    if !remove_key(&mut root, &key.split('.').collect::<Vec<&str>>()) {
+        return Err(NubError::MissingConfig(key).into());
+    }
+    write_file(path, root)
+}
+
+fn insert_key(map: &mut Map<String, Value>, parts: &[&str], value: &str) {
+    let Some((first, rest)) = parts.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        map.retain(|existing: &String, _| !existing.eq_ignore_ascii_case(first));
+        map.insert(first.to_string(), Value::String(value.to_string()));
+        return;
+    }
+    let name: String = map
+        .keys()
+        .find(|k: &&String| k.eq_ignore_ascii_case(first))
+        .cloned()
+        .unwrap_or_else(|| first.to_string());
+    let entry: &mut Value = map.entry(name).or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    if let Value::Object(inner) = entry {
+        insert_key(inner, rest, value);
+    }
+}
+
+fn remove_key(map: &mut Map<String, Value>, parts: &[&str]) -> bool {
+    let Some((first, rest)) = parts.split_first() else {
+        return false;
+    };
+    let Some(name) = map
+        .keys()
+        .find(|k: &&String| k.eq_ignore_ascii_case(first))
+        .cloned()
+    else {
+        return false;
+    };
+    if rest.is_empty() {
+        return map.remove(&name).is_some();
+    }
+    let Some(Value::Object(inner)) = map.get_mut(&name) else {
+        return false;
+    };
+    let removed: bool = remove_key(inner, rest);
+    if inner.is_empty() {
+        map.remove(&name);
+    }
+    removed
+}
+
+fn write_file(path: &Path, root: Map<String, Value>) -> Result<()> {
+    lockfile::write_atomic(
+        path,
+        serde_json::to_string_pretty(&Value::Object(root))?.as_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_layers_and_env_overrides() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let global: PathBuf = temp.path().join("global");
+        let local: PathBuf = temp.path().join("local");
+        fs::write(&global, r#"{"user": {"name": "Global", "email": "g@x"}}"#).unwrap();
+        fs::write(
+            &local,
+            r#"{"user": {"name": "Local"}, "gc": {"pruneExpire": "1d"}}"#,
+        )
+        .unwrap();
+
+        let files: Vec<(Level, PathBuf)> = vec![
+            (Level::System, temp.path().join("missing")),
+            (Level::Global, global.clone()),
+            (Level::Local, local.clone()),
+        ];
+        let vars: Vec<(String, String)> = vec![
+            ("NUB_CONFIG_USER_EMAIL".to_string(), "env@x".to_string()),
+            ("NUB_CONFIG_GC_REFLOGEXPIRE".to_string(), "2d".to_string()),
+            (
+                "NUB_CONFIG_MY_SECTION_KEY".to_string(),
+                "ignored".to_string(),
+            ),
+            ("OTHER".to_string(), "ignored".to_string()),
+        ];
+        let config: Config = Config::load_from(&files, vars).unwrap();
+
+        let identity: Author = config.identity();
+        assert_eq!(
+            (identity.name.as_str(), identity.email.as_str()),
+            ("Local", "env@x")
+        );
+        assert_eq!(config.get_str("gc.pruneExpire"), Some("1d"));
+        assert_eq!(config.get_str("gc.reflogExpire"), Some("2d"));
+        assert!(config.get("my.section_key").is_none());
+        assert!(config.get("my_section.key").is_none());
+        assert_eq!(
+            config.get("user.name").unwrap().origin,
+            Origin::File(Level::Local, local)
+        );
+        assert_eq!(
+            config.get("user.email").unwrap().origin.to_string(),
+            "env:NUB_CONFIG_USER_EMAIL"
+        );
+        assert!(config.get("core.editor").is_none());
+    }
+
+    #[test]
+    fn test_unreadable_config_is_an_error() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let missing: PathBuf = temp.path().join("missing");
+        assert!(Config::load_from(&[(Level::Global, missing)], Vec::new()).is_ok());
+        let directory: PathBuf = temp.path().to_path_buf();
+        assert!(Config::load_from(&[(Level::Global, directory)], Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_set_and_unset_values() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = temp.path().join("config");
+        set_value(&path, "user.name", "Ada").unwrap();
+        set_value(&path, "core.autoCrlf", "no").unwrap();
+        set_value(&path, "User.Name", "Grace").unwrap();
+        assert!(set_value(&path, "nodot", "x").is_err());
+
+        let config: Config =
+            Config::load_from(&[(Level::Local, path.clone())], Vec::new()).unwrap();
+        assert_eq!(config.get_str("user.name"), Some("Grace"));
+        assert_eq!(config.get_bool("core.autocrlf").unwrap(), Some(false));
+
+        unset_value(&path, "user.name").unwrap();
+        assert!(unset_value(&path, "user.name").is_err());
+        let config: Config = Config::load_from(&[(Level::Local, path)], Vec::new()).unwrap();
+        assert_eq!(config.get_str("user.name"), None);
+        assert_eq!(config.entries().count(), 1);
+    }
+}
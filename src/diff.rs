@@ -0,0 +1,536 @@
+use colored::Colorize;
+
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+#[derive(Debug)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub edits: Vec<Edit>,
+}
+
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+pub fn is_binary(content: &[u8]) -> bool {
+    content.contains(&0) || std::str::from_utf8(content).is_err()
+}
+
+pub fn myers<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, 0, 0, &mut edits);
+    edits
+}
+
+fn diff_range<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    old_start: usize,
+    new_start: usize,
+    edits: &mut Vec<Edit>,
+) {
+    let prefix: usize = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b): &(&T, &T)| a == b)
+        .count();
+    for i in 0..prefix {
+        edits.push(Edit::Equal(old_start + i, new_start + i));
+    }
+    let old: &[T] = &old[prefix..];
+    let new: &[T] = &new[prefix..];
+    let (old_start, new_start): (usize, usize) = (old_start + prefix, new_start + prefix);
+
+    let suffix: usize = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b): &(&T, &T)| a == b)
+        .count();
+    let old: &[T] = &old[..old.len() - suffix];
+    let new: &[T] = &new[..new.len() - suffix];
+
+    if old.is_empty() {
+        edits.extend((0..new.len()).map(|j: usize| Edit::Insert(new_start + j)));
+    } else if new.is_empty() {
+        edits.extend((0..old.len()).map(|i: usize| Edit::Delete(old_start + i)));
+    } else {
+        let (x, y, u, v) = middle_snake(old, new);
+        diff_range(&old[..x], &new[..y], old_start, new_start, edits);
+        for i in 0..u - x {
+            edits.push(Edit::Equal(old_start + x + i, new_start + y + i));
+        }
+        diff_range(&old[u..], &new[v..], old_start + u, new_start + v, edits);
+    }
+
+    let (old_end, new_end): (usize, usize) = (old_start + old.len(), new_start + new.len());
+    for i in 0..suffix {
+        edits.push(Edit::Equal(old_end + i, new_end + i));
+    }
+}
+
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize, usize, usize) {
+    let n: isize = old.len() as isize;
+    let m: isize = new.len() as isize;
+    let max: isize = (n + m + 1) / 2;
+    let offset: isize = max + 1;
+    let delta: isize = n - m;
+    let odd: bool = delta % 2 != 0;
+    let mut forward: Vec<isize> = vec![0; 2 * offset as usize + 1];
+    let mut backward: Vec<isize> = vec![0; 2 * offset as usize + 1];
+
+    for d in 0..=max {
+        let mut k: isize = -d;
+        while k <= d {
+            let idx: usize = (k + offset) as usize;
+            let mut x: isize = if k == -d || (k != d && forward[idx - 1] < forward[idx + 1]) {
+                forward[idx + 1]
+            } else {
+                forward[idx - 1] + 1
+            };
+            let mut y: isize = x - k;
+            let (start_x, start_y): (isize, isize) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[idx] = x;
+
+            let reverse_k: isize = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[(reverse_k + offset) as usize] >= n {
+                return (start_x as usize, start_y as usize, x as usize, y as usize);
+            }
+            k += 2;
+        }
+
+        let mut k: isize = -d;
+        while k <= d {
+            let idx: usize = (k + offset) as usize;
+            let mut x: isize = if k == -d || (k != d && backward[idx - 1] < backward[idx + 1]) {
+                backward[idx + 1]
+            } else {
+                backward[idx - 1] + 1
+            };
+            let mut y: isize = x - k;
+            let (start_x, start_y): (isize, isize) = (x, y);
+            while x < n && y < m && old[(n - 1 - x) as usize] == new[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[idx] = x;
+
+            let forward_k: isize = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[(forward_k + offset) as usize] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - start_x) as usize,
+                    (m - start_y) as usize,
+                );
+            }
+            k += 2;
+        }
+    }
+
+    (0, 0, 0, 0)
+}
+
+pub fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk> {
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Equal(_, _)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start: usize = i.saturating_sub(context);
+        let end: usize = (i + context + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let slice: Vec<Edit> = edits[start..end].to_vec();
+            let (mut old_start, mut new_start) = position_before(edits, start);
+            let old_len: usize = slice
+                .iter()
+                .filter(|e| !matches!(e, Edit::Insert(_)))
+                .count();
+            let new_len: usize = slice
+                .iter()
+                .filter(|e| !matches!(e, Edit::Delete(_)))
+                .count();
+            if old_len > 0 {
+                old_start += 1;
+            }
+            if new_len > 0 {
+                new_start += 1;
+            }
+            Hunk {
+                old_start,
+                old_len,
+                new_start,
+                new_len,
+                edits: slice,
+            }
+        })
+        .collect()
+}
+
+fn position_before(edits: &[Edit], index: usize) -> (usize, usize) {
+    let mut old_pos: usize = 0;
+    let mut new_pos: usize = 0;
+    for edit in &edits[..index] {
+        match edit {
+            Edit::Equal(_, _) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Delete(_) => old_pos += 1,
+            Edit::Insert(_) => new_pos += 1,
+        }
+    }
+    (old_pos, new_pos)
+}
+
+pub fn line_counts(old: &[u8], new: &[u8]) -> Option<(usize, usize)> {
+    if is_binary(old) || is_binary(new) {
+        return None;
+    }
+    let old_text: String = String::from_utf8_lossy(old).into_owned();
+    let new_text: String = String::from_utf8_lossy(new).into_owned();
+    let edits: Vec<Edit> = myers(&split_lines(&old_text), &split_lines(&new_text));
+    let added: usize = edits
+        .iter()
+        .filter(|edit: &&Edit| matches!(edit, Edit::Insert(_)))
+        .count();
+    let removed: usize = edits
+        .iter()
+        .filter(|edit: &&Edit| matches!(edit, Edit::Delete(_)))
+        .count();
+    Some((added, removed))
+}
+
+pub fn print_file_diff(path: &str, old: Option<&[u8]>, new: Option<&[u8]>) {
+    let old_label: String = if old.is_some() {
+        format!("a/{}", path)
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_label: String = if new.is_some() {
+        format!("b/{}", path)
+    } else {
+        "/dev/null".to_string()
+    };
+
+    println!("{}", format!("diff --nub a/{} b/{}", path, path).bold());
+    match (old, new) {
+        (None, Some(_)) => println!("{}", "new file".bold()),
+        (Some(_), None) => println!("{}", "deleted file".bold()),
+        _ => {}
+    }
+
+    let old_bytes: &[u8] = old.unwrap_or(&[]);
+    let new_bytes: &[u8] = new.unwrap_or(&[]);
+
+    if is_binary(old_bytes) || is_binary(new_bytes) {
+        println!("Binary files {} and {} differ", old_label, new_label);
+        return;
+    }
+
+    println!("{}", format!("--- {}", old_label).bold());
+    println!("{}", format!("+++ {}", new_label).bold());
+
+    let old_text: String = String::from_utf8_lossy(old_bytes).into_owned();
+    let new_text: String = String::from_utf8_lossy(new_bytes).into_owned();
+    let old_lines: Vec<&str> = split_lines(&old_text);
+    let new_lines: Vec<&str> = split_lines(&new_text);
+
+    let edits: Vec<Edit> = myers(&old_lines, &new_lines);
+    for hunk in hunks(&edits, CONTEXT_LINES) {
+        println!(
+            "{}",
+            format!(
+                "@@ -{},{} +{},{} @@",
+                hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len
+            )
+            .cyan()
+        );
+        for edit in &hunk.edits {
+            match *edit {
+                Edit::Equal(i, _) => print_line(" ", old_lines[i], None),
+                Edit::Delete(i) => print_line("-", old_lines[i], Some(false)),
+                Edit::Insert(j) => print_line("+", new_lines[j], Some(true)),
+            }
+        }
+    }
+}
+
+fn print_line(prefix: &str, line: &str, added: Option<bool>) {
+    let text: String = format!("{}{}", prefix, line.trim_end_matches('\n'));
+    match added {
+        Some(true) => println!("{}", text.green()),
+        Some(false) => println!("{}", text.red()),
+        None => println!("{}", text),
+    }
+    if !line.ends_with('\n') {
+        println!("{}", "\\ No newline at end of file".dimmed());
+    }
+}
+
+pub struct MergeResult {
+    pub content: String,
+    pub conflicts: usize,
+}
+
+fn matches(edits: &[Edit], len: usize) -> Vec<Option<usize>> {
+    let mut map: Vec<Option<usize>> = vec![None; len];
+    for edit in edits {
+        if let Edit::Equal(i, j) = *edit {
+            map[i] = Some(j);
+        }
+    }
+    map
+}
+
+pub fn merge3(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    our_label: &str,
+    their_label: &str,
+) -> MergeResult {
+    let base_lines: Vec<&str> = split_lines(base);
+    let our_lines: Vec<&str> = split_lines(ours);
+    let their_lines: Vec<&str> = split_lines(theirs);
+
+    let ours_match: Vec<Option<usize>> = matches(&myers(&base_lines, &our_lines), base_lines.len());
+    let theirs_match: Vec<Option<usize>> =
+        matches(&myers(&base_lines, &their_lines), base_lines.len());
+
+    let mut content: String = String::new();
+    let mut conflicts: usize = 0;
+    let (mut ib, mut io, mut it) = (0usize, 0usize, 0usize);
+
+    while ib < base_lines.len() || io < our_lines.len() || it < their_lines.len() {
+        let mut stable: usize = 0;
+        while ib + stable < base_lines.len()
+            && ours_match[ib + stable] == Some(io + stable)
+            && theirs_match[ib + stable] == Some(it + stable)
+        {
+            stable += 1;
+        }
+
+        if stable > 0 {
+            for line in &base_lines[ib..ib + stable] {
+                content.push_str(line);
+            }
+            ib += stable;
+            io += stable;
+            it += stable;
+            continue;
+        }
+
+        let next_stable: Option<usize> = (ib..base_lines.len())
+            .find(|&k: &usize| ours_match[k].is_some() && theirs_match[k].is_some());
+        let (base_end, our_end, their_end) = match next_stable {
+            Some(k) => (k, ours_match[k].unwrap(), theirs_match[k].unwrap()),
+            None => (base_lines.len(), our_lines.len(), their_lines.len()),
+        };
+
+        let base_chunk: &[&str] = &base_lines[ib..base_end];
+        let our_chunk: &[&str] = &our_lines[io..our_end];
+        let their_chunk: &[&str] = &their_lines[it..their_end];
+
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            content.extend(their_chunk.iter().copied());
+        } else if their_chunk == base_chunk {
+            content.extend(our_chunk.iter().copied());
+        } else {
+            conflicts += 1;
+            push_marker(&mut content, &format!("<<<<<<< {}", our_label));
+            content.extend(our_chunk.iter().copied());
+            push_marker(&mut content, "=======");
+            content.extend(their_chunk.iter().copied());
+            push_marker(&mut content, &format!(">>>>>>> {}", their_label));
+        }
+
+        ib = base_end;
+        io = our_end;
+        it = their_end;
+    }
+
+    MergeResult { content, conflicts }
+}
+
+fn push_marker(content: &mut String, marker: &str) {
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(marker);
+    content.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(old: &[&str], new: &[&str], edits: &[Edit]) -> Vec<String> {
+        edits
+            .iter()
+            .filter_map(|e| match *e {
+                Edit::Equal(i, _) => Some(old[i].to_string()),
+                Edit::Insert(j) => Some(new[j].to_string()),
+                Edit::Delete(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_counts() {
+        assert_eq!(line_counts(b"a\nb\nc\n", b"a\nB\nc\nd\n"), Some((2, 1)));
+        assert_eq!(line_counts(b"", b"x\n"), Some((1, 0)));
+        assert_eq!(line_counts(b"a\0b", b"a"), None);
+    }
+
+    #[test]
+    fn test_myers_identical() {
+        let lines: Vec<&str> = vec!["a", "b", "c"];
+        let edits: Vec<Edit> = myers(&lines, &lines);
+        assert!(edits.iter().all(|e| matches!(e, Edit::Equal(_, _))));
+        assert_eq!(edits.len(), 3);
+    }
+
+    #[test]
+    fn test_myers_reconstructs_new() {
+        let old: Vec<&str> = vec!["a", "b", "c", "a", "b", "b", "a"];
+        let new: Vec<&str> = vec!["c", "b", "a", "b", "a", "c"];
+        let edits: Vec<Edit> = myers(&old, &new);
+        assert_eq!(apply(&old, &new, &edits), new);
+        let changes: usize = edits
+            .iter()
+            .filter(|e| !matches!(e, Edit::Equal(_, _)))
+            .count();
+        assert_eq!(changes, 5);
+    }
+
+    #[test]
+    fn test_myers_is_minimal_on_random_inputs() {
+        let mut seed: u64 = 42;
+        let mut next = |limit: u64| -> u64 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % limit
+        };
+        for _ in 0..500 {
+            let old: Vec<u64> = (0..next(12)).map(|_| next(4)).collect();
+            let new: Vec<u64> = (0..next(12)).map(|_| next(4)).collect();
+
+            let mut lcs: Vec<Vec<usize>> = vec![vec![0; new.len() + 1]; old.len() + 1];
+            for i in (0..old.len()).rev() {
+                for j in (0..new.len()).rev() {
+                    lcs[i][j] = if old[i] == new[j] {
+                        lcs[i + 1][j + 1] + 1
+                    } else {
+                        lcs[i + 1][j].max(lcs[i][j + 1])
+                    };
+                }
+            }
+
+            let edits: Vec<Edit> = myers(&old, &new);
+            let rebuilt: Vec<u64> = edits
+                .iter()
+                .filter_map(|e| match *e {
+                    Edit::Equal(i, j) => {
+                        assert_eq!(old[i], new[j]);
+                        Some(old[i])
+                    }
+                    Edit::Insert(j) => Some(new[j]),
+                    Edit::Delete(_) => None,
+                })
+                .collect();
+            assert_eq!(rebuilt, new);
+            let equal: usize = edits
+                .iter()
+                .filter(|e| matches!(e, Edit::Equal(_, _)))
+                .count();
+            assert_eq!(equal, lcs[0][0]);
+        }
+    }
+
+    #[test]
+    fn test_myers_handles_large_rewrites() {
+        let old: Vec<usize> = (0..3_000).collect();
+        let new: Vec<usize> = (3_000..6_000).collect();
+        let edits: Vec<Edit> = myers(&old, &new);
+        assert_eq!(edits.len(), 6_000);
+    }
+
+    #[test]
+    fn test_myers_empty_sides() {
+        let new: Vec<&str> = vec!["x", "y"];
+        assert_eq!(
+            myers::<&str>(&[], &new),
+            vec![Edit::Insert(0), Edit::Insert(1)]
+        );
+        assert_eq!(
+            myers::<&str>(&new, &[]),
+            vec![Edit::Delete(0), Edit::Delete(1)]
+        );
+    }
+
+    #[test]
+    fn test_merge3_combines_independent_changes() {
+        let base: &str = "a\nb\nc\nd\ne\n";
+        let ours: &str = "A\nb\nc\nd\ne\n";
+        let theirs: &str = "a\nb\nc\nd\nE\nf\n";
+        let result: MergeResult = merge3(base, ours, theirs, "HEAD", "topic");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.content, "A\nb\nc\nd\nE\nf\n");
+    }
+
+    #[test]
+    fn test_merge3_marks_overlapping_changes() {
+        let base: &str = "a\nb\nc\n";
+        let ours: &str = "a\nours\nc\n";
+        let theirs: &str = "a\ntheirs\nc\n";
+        let result: MergeResult = merge3(base, ours, theirs, "HEAD", "topic");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.content,
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_hunks_merge_nearby_changes() {
+        let old: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut new: Vec<String> = old.clone();
+        new[2] = "two".to_string();
+        new[5] = "five".to_string();
+        new[17] = "seventeen".to_string();
+
+        let edits: Vec<Edit> = myers(&old, &new);
+        let result: Vec<Hunk> = hunks(&edits, 3);
+        assert_eq!(result.len(), 2);
+        assert_eq!((result[0].old_start, result[0].old_len), (1, 9));
+        assert_eq!((result[1].new_start, result[1].new_len), (15, 6));
+    }
+}
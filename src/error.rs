@@ -6,6 +6,40 @@ pub enum NubError {
     RepositoryNotFound,
     InvalidRepository,
     FileNotFound(String),
+    PathspecNotMatched(String),
+    ObjectNotFound(String),
+    CorruptObject(String),
+    UnexpectedObjectType(String, String),
+    LegacyLayout,
+    RevisionNotFound(String),
+    AmbiguousRevision(String),
+    WouldOverwrite(Vec<String>),
+    UncommittedChanges(Vec<String>),
+    PathOutsideRepository(String),
+    NotTracked(String),
+    DestinationExists(String),
+    InvalidBranchName(String),
+    BranchAlreadyExists(String),
+    BranchNotFound(String),
+    BranchNotMerged(String),
+    CannotDeleteCurrentBranch(String),
+    InvalidTagName(String),
+    TagAlreadyExists(String),
+    TagNotFound(String),
+    StashNotFound(String),
+    MergeInProgress,
+    UnresolvedConflicts(Vec<String>),
+    CorruptIndex(String),
+    IndexEntryTooLong(String),
+    LockHeld(String),
+    RefChanged(String),
+    IntegrityCheckFailed(usize),
+    MissingConfig(String),
+    NoHomeDirectory,
+    InvalidConfigKey(String),
+    InvalidConfigValue(String, String),
+    CorruptConfig(String, String),
+    InvalidExpiry(String),
     IoError(std::io::Error),
     SerializationError(String),
 }
@@ -25,6 +59,149 @@ impl fmt::Display for NubError {
             NubError::FileNotFound(path) => {
                 write!(f, "File not found: {}", path)
             }
+            NubError::PathspecNotMatched(spec) => {
+                write!(f, "Pathspec '{}' did not match any file known to nub", spec)
+            }
+            NubError::ObjectNotFound(hash) => {
+                write!(f, "Object not found: {}", hash)
+            }
+            NubError::CorruptObject(hash) => {
+                write!(f, "Object is corrupt: {}", hash)
+            }
+            NubError::UnexpectedObjectType(hash, expected) => {
+                write!(f, "Object {} is not a {}", hash, expected)
+            }
+            NubError::LegacyLayout => {
+                write!(
+                    f,
+                    "Repository uses the old object layout, run `nub migrate` to upgrade it"
+                )
+            }
+            NubError::RevisionNotFound(rev) => {
+                write!(f, "Unknown revision: {}", rev)
+            }
+            NubError::AmbiguousRevision(rev) => {
+                write!(f, "Ambiguous revision: {}", rev)
+            }
+            NubError::WouldOverwrite(paths) => {
+                write!(f, "Your local changes to these files would be overwritten:")?;
+                for path in paths {
+                    write!(f, "\n  {}", path)?;
+                }
+                write!(f, "\nCommit them first or use --force")
+            }
+            NubError::UncommittedChanges(paths) => {
+                write!(f, "These files have uncommitted changes:")?;
+                for path in paths {
+                    write!(f, "\n  {}", path)?;
+                }
+                write!(
+                    f,
+                    "\nUse --cached to keep them on disk or --force to remove them"
+                )
+            }
+            NubError::PathOutsideRepository(path) => {
+                write!(f, "Path is outside the repository: {}", path)
+            }
+            NubError::NotTracked(path) => {
+                write!(f, "Path is not tracked: {}", path)
+            }
+            NubError::DestinationExists(path) => {
+                write!(
+                    f,
+                    "Destination already exists: {} (use -f to overwrite)",
+                    path
+                )
+            }
+            NubError::InvalidBranchName(name) => {
+                write!(f, "Invalid branch name: {}", name)
+            }
+            NubError::BranchAlreadyExists(name) => {
+                write!(f, "Branch already exists: {}", name)
+            }
+            NubError::BranchNotFound(name) => {
+                write!(f, "Branch not found: {}", name)
+            }
+            NubError::BranchNotMerged(name) => {
+                write!(
+                    f,
+                    "Branch {} is not fully merged, use -D to delete it anyway",
+                    name
+                )
+            }
+            NubError::CannotDeleteCurrentBranch(name) => {
+                write!(f, "Cannot delete the checked out branch: {}", name)
+            }
+            NubError::InvalidTagName(name) => {
+                write!(f, "Invalid tag name: {}", name)
+            }
+            NubError::TagAlreadyExists(name) => {
+                write!(f, "Tag already exists: {} (use -f to replace it)", name)
+            }
+            NubError::TagNotFound(name) => {
+                write!(f, "Tag not found: {}", name)
+            }
+            NubError::StashNotFound(name) => {
+                write!(f, "No stash entry found: {}", name)
+            }
+            NubError::MergeInProgress => {
+                write!(f, "A merge is in progress, commit it before merging again")
+            }
+            NubError::UnresolvedConflicts(paths) => {
+                write!(f, "Unresolved merge conflicts, fix and add these files:")?;
+                for path in paths {
+                    write!(f, "\n  {}", path)?;
+                }
+                Ok(())
+            }
+            NubError::CorruptIndex(reason) => {
+                write!(f, "Index file is corrupt: {}", reason)
+            }
+            NubError::IndexEntryTooLong(value) => {
+                write!(f, "Too long to store in the index: {}", value)
+            }
+            NubError::LockHeld(path) => {
+                write!(
+                    f,
+                    "Unable to lock {}: another nub process seems to be running.\nIf none is, remove the lock file and try again",
+                    path
+                )
+            }
+            NubError::RefChanged(name) => {
+                write!(f, "{} was updated by another process, try again", name)
+            }
+            NubError::IntegrityCheckFailed(count) => {
+                write!(f, "Repository check found {} problems", count)
+            }
+            NubError::MissingConfig(key) => {
+                write!(
+                    f,
+                    "Missing config value: {} (set it with `nub config set {} <value>`)",
+                    key, key
+                )
+            }
+            NubError::NoHomeDirectory => {
+                write!(
+                    f,
+                    "No home directory for the global config (set HOME or NUB_GLOBAL_CONFIG)"
+                )
+            }
+            NubError::InvalidConfigKey(key) => {
+                write!(f, "Invalid config key: {} (expected section.name)", key)
+            }
+            NubError::InvalidConfigValue(key, value) => {
+                write!(f, "Invalid value for {}: {}", key, value)
+            }
+            NubError::CorruptConfig(path, reason) => {
+                write!(f, "Config file {} is corrupt: {}", path, reason)
+            }
+            NubError::InvalidExpiry(value) => {
+                write!(
+                    f,
+                    "Invalid expiry: {} (use e.g. now, 30m, 12h, 14d or 2w)",
+                    value
+                )
+            }
             NubError::IoError(err) => {
                 write!(f, "IO error: {}", err)
             }
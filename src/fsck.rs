@@ -0,0 +1,381 @@
+use crate::objects::tree::EntryType;
+use crate::objects::{Commit, ObjectType, Tag, Tree, decode_object, hash_object};
+use crate::pack::{self, Pack};
+use crate::reflog::ReflogEntry;
+use crate::repository::{Head, Repository};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Problem {
+    Corrupt(String, String),
+    Missing(ObjectType, String, String),
+    WrongType(String, ObjectType, ObjectType, String),
+    BrokenRef(String, String),
+    CorruptPack(String, String),
+    Unreadable(String, String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Corrupt(hash, reason) => write!(f, "corrupt object {}: {}", hash, reason),
+            Problem::Missing(kind, hash, referenced_by) => {
+                write!(
+                    f,
+                    "missing {} {} (referenced by {})",
+                    kind, hash, referenced_by
+                )
+            }
+            Problem::WrongType(hash, expected, actual, referenced_by) => write!(
+                f,
+                "object {} is a {} but {} expects a {}",
+                hash, actual, referenced_by, expected
+            ),
+            Problem::BrokenRef(name, hash) => {
+                write!(f, "{} points at missing commit or tag {}", name, hash)
+            }
+            Problem::CorruptPack(name, reason) => write!(f, "corrupt pack {}: {}", name, reason),
+            Problem::Unreadable(what, reason) => write!(f, "cannot read {}: {}", what, reason),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    pub checked: usize,
+    pub problems: Vec<Problem>,
+    pub dangling: Vec<(ObjectType, String)>,
+    pub unreachable: Vec<(ObjectType, String)>,
+}
+
+type Links = Vec<(ObjectType, String)>;
+
+fn parse_links(kind: ObjectType, body: &[u8]) -> Option<Links> {
+    match kind {
+        ObjectType::Blob => Some(Vec::new()),
+        ObjectType::Tree => {
+            let tree: Tree = serde_json::from_slice(body).ok()?;
+            Some(
+                tree.entries
+                    .into_iter()
+                    .map(|entry| match entry.entry_type {
+                        EntryType::Blob => (ObjectType::Blob, entry.hash),
+                        EntryType::Tree => (ObjectType::Tree, entry.hash),
+                    })
+                    .collect(),
+            )
+        }
+        ObjectType::Commit => {
+            let commit: Commit = serde_json::from_slice(body).ok()?;
+            let mut links: Links = vec![(ObjectType::Tree, commit.tree)];
+            links.extend(
+                commit
+                    .parents
+                    .into_iter()
+                    .map(|parent: String| (ObjectType::Commit, parent)),
+            );
+            Some(links)
+        }
+        ObjectType::Tag => {
+            let tag: Tag = serde_json::from_slice(body).ok()?;
+            Some(vec![(ObjectType::parse(&tag.object_type)?, tag.object)])
+        }
+    }
+}
+
+pub fn check(repo: &Repository) -> Result<FsckReport> {
+    let mut report: FsckReport = FsckReport::default();
+    let mut objects: BTreeMap<String, (ObjectType, Links)> = BTreeMap::new();
+    let mut corrupt: HashSet<String> = HashSet::new();
+
+    let pack_dir: PathBuf = repo.objects().pack_dir();
+    for name in pack::list_packs(&pack_dir)? {
+        if let Err(err) = Pack::open(&pack_dir, &name).and_then(|pack: Pack| pack.verify()) {
+            report
+                .problems
+                .push(Problem::CorruptPack(name, err.to_string()));
+        }
+    }
+    // A pack that cannot be opened hides every object in it; still check
+    // the loose objects rather than giving up.
+    let hashes: Vec<String> = match repo.objects().hashes() {
+        Ok(hashes) => hashes,
+        Err(_) => repo.objects().loose_hashes()?,
+    };
+
+    for hash in hashes {
+        report.checked += 1;
+        let problem: Option<String> = match repo.objects().read(&hash) {
+            Err(err) => Some(format!("unreadable ({})", err)),
+            Ok(None) => Some("vanished while checking".to_string()),
+            Ok(Some(data)) => match decode_object(&data) {
+                None => Some("invalid header".to_string()),
+                Some((kind, body)) if hash_object(kind, body) != hash => {
+                    Some("content does not match its hash".to_string())
+                }
+                Some((kind, body)) => match parse_links(kind, body) {
+                    Some(links) => {
+                        objects.insert(hash.clone(), (kind, links));
+                        None
+                    }
+                    None => Some(format!("invalid {} contents", kind)),
+                },
+            },
+        };
+        if let Some(reason) = problem {
+            report.problems.push(Problem::Corrupt(hash.clone(), reason));
+            corrupt.insert(hash);
+        }
+    }
+
+    let mut roots: Vec<(String, String)> = repo.list_refs()?;
+    if let Head::Detached(hash) = repo.read_head()? {
+        roots.push(("HEAD".to_string(), hash));
+    }
+    if let Ok(merge_head) = fs::read_to_string(repo.merge_head_path()) {
+        roots.push(("MERGE_HEAD".to_string(), merge_head.trim().to_string()));
+    }
+
+    let mut pending: Vec<(ObjectType, String, String)> = Vec::new();
+    for (name, hash) in roots {
+        match objects.get(&hash) {
+            Some((kind @ (ObjectType::Commit | ObjectType::Tag), _)) => {
+                pending.push((*kind, hash, name))
+            }
+            Some(_) | None if !corrupt.contains(&hash) => {
+                report.problems.push(Problem::BrokenRef(name, hash))
+            }
+            _ => {}
+        }
+    }
+    for refname in repo.list_reflogs()? {
+        // Read the log directly: the normal reader skips lines it cannot
+        // parse, which is exactly what fsck needs to report.
+        let what: String = format!("reflog {}", refname);
+        let data: String = match fs::read_to_string(repo.reflog_path(&refname)) {
+            Ok(data) => data,
+            Err(err) => {
+                report
+                    .problems
+                    .push(Problem::Unreadable(what, err.to_string()));
+                continue;
+            }
+        };
+        let mut entries: Vec<ReflogEntry> = Vec::new();
+        for (number, line) in data.lines().enumerate() {
+            match ReflogEntry::parse(line) {
+                Some(entry) => entries.push(entry),
+                None => report.problems.push(Problem::Unreadable(
+                    what.clone(),
+                    format!("line {} is malformed", number + 1),
+                )),
+            }
+        }
+        for entry in entries {
+            for hash in [entry.old_hash(), Some(entry.new.as_str())]
+                .into_iter()
+                .flatten()
+            {
+                if let Some((ObjectType::Commit, _)) = objects.get(hash) {
+                    pending.push((
+                        ObjectType::Commit,
+                        hash.to_string(),
+                        format!("reflog {}", refname),
+                    ));
+                }
+            }
+        }
+    }
+    match repo.read_index() {
+        Ok(index) => {
+            for (path, hash) in index.entries() {
+                if let Some(hash) = hash {
+                    pending.push((
+                        ObjectType::Blob,
+                        hash.clone(),
+                        format!("index entry {}", path),
+                    ));
+                }
+            }
+        }
+        Err(err) => report
+            .problems
+            .push(Problem::Unreadable("index".to_string(), err.to_string())),
+    }
+
+    let mut reachable: HashSet<String> = HashSet::new();
+    while let Some((expected, hash, referenced_by)) = pending.pop() {
+        if corrupt.contains(&hash) {
+            continue;
+        }
+        let Some((kind, links)) = objects.get(&hash) else {
+            report
+                .problems
+                .push(Problem::Missing(expected, hash, referenced_by));
+            continue;
+        };
+        if *kind != expected {
+            report
+                .problems
+                .push(Problem::WrongType(hash, expected, *kind, referenced_by));
+            continue;
+        }
+        if !reachable.insert(hash.clone()) {
+            continue;
+        }
+        for (link_kind, link) in links {
+            pending.push((*link_kind, link.clone(), format!("{} {}", kind, hash)));
+        }
+    }
+
+    let referenced: HashSet<&String> = objects
+        .values()
+        .flat_map(|(_, links): &(ObjectType, Links)| links.iter().map(|(_, hash)| hash))
+        .collect();
+    for (hash, (kind, _)) in &objects {
+        if reachable.contains(hash) {
+            continue;
+        }
+        if referenced.contains(hash) {
+            report.unreachable.push((*kind, hash.clone()));
+        } else {
+            report.dangling.push((*kind, hash.clone()));
+        }
+    }
+
+    let problems: BTreeSet<Problem> = report.problems.drain(..).collect();
+    report.problems = problems.into_iter().collect();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Object;
+    use crate::objects::commit::Author;
+    use crate::pack::PackObject;
+    use std::collections::HashMap;
+
+    const PACK_BODY_BYTE: usize = 20;
+    use tempfile::TempDir;
+
+    fn commit(repo: &Repository, files: &HashMap<String, String>, parents: Vec<String>) -> String {
+        let tree: String = repo.write_tree_from_files(files).unwrap();
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        repo.write_object(&Object::Commit(Commit::new(
+            tree,
+            parents,
+            author,
+            "message".to_string(),
+        )))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_clean_repository_has_no_problems() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("src/a.txt".to_string(), repo.write_blob(b"a").unwrap());
+        let first: String = commit(&repo, &files, Vec::new());
+        repo.update_head(&first, None, "commit").unwrap();
+
+        let report: FsckReport = check(&repo).unwrap();
+        assert!(report.problems.is_empty());
+        assert!(report.dangling.is_empty());
+        assert_eq!(report.checked, 4);
+    }
+
+    #[test]
+    fn test_reports_missing_corrupt_and_dangling_objects() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+
+        let missing: String = hash_object(ObjectType::Blob, b"never written");
+        let corrupted: String = repo.write_blob(b"original").unwrap();
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("gone.txt".to_string(), missing.clone());
+        files.insert("bad.txt".to_string(), corrupted.clone());
+        let head: String = commit(&repo, &files, Vec::new());
+        repo.update_head(&head, None, "commit").unwrap();
+        repo.write_branch(
+            "broken",
+            &hash_object(ObjectType::Commit, b"nope"),
+            "branch",
+        )
+        .unwrap();
+
+        let orphan: String = commit(&repo, &HashMap::new(), Vec::new());
+
+        fs::remove_file(repo.objects().path(&corrupted)).unwrap();
+        repo.objects()
+            .write(&corrupted, b"blob 8\0tampered")
+            .unwrap();
+
+        let report: FsckReport = check(&repo).unwrap();
+        assert_eq!(report.problems.len(), 3);
+        assert!(report.problems.contains(&Problem::Corrupt(
+            corrupted.clone(),
+            "content does not match its hash".to_string()
+        )));
+        assert!(report.problems.iter().any(|p: &Problem| matches!(
+            p,
+            Problem::Missing(ObjectType::Blob, hash, _) if hash == &missing
+        )));
+        assert!(report.problems.iter().any(|p: &Problem| matches!(
+            p,
+            Problem::BrokenRef(name, _) if name == "refs/heads/broken"
+        )));
+        assert_eq!(report.dangling, vec![(ObjectType::Commit, orphan)]);
+        assert_eq!(report.unreachable.len(), 1);
+    }
+
+    #[test]
+    fn test_reports_unreadable_index_reflog_and_pack() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let mut repo: Repository = Repository::init(temp.path()).unwrap();
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("a.txt".to_string(), repo.write_blob(b"a").unwrap());
+        let head: String = commit(&repo, &files, Vec::new());
+        repo.update_head(&head, None, "commit").unwrap();
+
+        let body: Vec<u8> = b"packed".to_vec();
+        let (name, _) = repo
+            .objects_mut()
+            .write_pack(&[PackObject {
+                hash: hash_object(ObjectType::Blob, &body),
+                kind: ObjectType::Blob,
+                body,
+                name_hint: String::new(),
+            }])
+            .unwrap();
+        let pack_path: PathBuf = repo.objects().pack_dir().join(format!("{}.pack", name));
+        let mut data: Vec<u8> = fs::read(&pack_path).unwrap();
+        data[PACK_BODY_BYTE] ^= 0xff;
+        fs::write(&pack_path, data).unwrap();
+
+        fs::write(repo.index_path(), b"garbage").unwrap();
+        fs::write(repo.reflog_path("refs/heads/main"), b"garbage\n").unwrap();
+
+        let report: FsckReport = check(&repo).unwrap();
+        assert!(report.problems.iter().any(|p: &Problem| matches!(
+            p,
+            Problem::CorruptPack(pack, _) if pack == &name
+        )));
+        assert!(report.problems.iter().any(|p: &Problem| matches!(
+            p,
+            Problem::Unreadable(what, _) if what == "index"
+        )));
+        assert!(report.problems.iter().any(|p: &Problem| matches!(
+            p,
+            Problem::Unreadable(what, _) if what == "reflog refs/heads/main"
+        )));
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE: &str = ".nubignore";
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub source: String,
+    pub line: usize,
+    pub text: String,
+    pub negated: bool,
+    base: String,
+    pattern: Vec<char>,
+    dir_only: bool,
+}
+
+impl Rule {
+    fn parse(source: &str, base: &str, line: usize, raw: &str) -> Option<Rule> {
+        let mut text: &str = raw.trim_end_matches(['\r', '\n']);
+        if !text.ends_with("\\ ") {
+            text = text.trim_end_matches(' ');
+        }
+        if text.is_empty() || text.starts_with('#') {
+            return None;
+        }
+
+        let negated: bool = text.starts_with('!');
+        let pattern: &str = if negated || text.starts_with("\\!") || text.starts_with("\\#") {
+            &text[1..]
+        } else {
+            text
+        };
+
+        let dir_only: bool = pattern.ends_with('/');
+        let pattern: &str = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let anchored: bool = pattern.contains('/');
+        let pattern: &str = pattern.trim_start_matches('/');
+        let full: String = if anchored || pattern.starts_with("**/") {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+
+        Some(Rule {
+            source: source.to_string(),
+            line,
+            text: text.to_string(),
+            negated,
+            base: base.to_string(),
+            pattern: full.chars().collect(),
+            dir_only,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative: &str = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&self.base) {
+                Some(rest) if rest.starts_with('/') => &rest[1..],
+                _ => return false,
+            }
+        };
+        let text: Vec<char> = relative.chars().collect();
+        glob_match(&self.pattern, &text)
+    }
+}
+
+pub fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest: &[char] = &pattern[2..];
+            if rest.first() == Some(&'/') {
+                let rest: &[char] = &rest[1..];
+                glob_match(rest, text)
+                    || (0..text.len())
+                        .any(|i: usize| text[i] == '/' && glob_match(rest, &text[i + 1..]))
+            } else {
+                (0..=text.len()).any(|i: usize| glob_match(rest, &text[i..]))
+            }
+        }
+        Some('*') => {
+            let rest: &[char] = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && glob_match(&pattern[1..], &text[1..]),
+        Some('[') => match match_class(&pattern[1..], text.first().copied()) {
+            Some((true, consumed)) => glob_match(&pattern[1 + consumed..], &text[1..]),
+            Some((false, _)) => false,
+            None => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+fn match_class(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let mut i: usize = 0;
+    let negated: bool = matches!(class.first(), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched: bool = false;
+    let mut first: bool = true;
+    while i < class.len() {
+        if class[i] == ']' && !first {
+            let c: char = c?;
+            if c == '/' {
+                return Some((false, i + 1));
+            }
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+
+        let low: char = class[i];
+        if i + 2 < class.len() && class[i + 1] == '-' && class[i + 2] != ']' {
+            let high: char = class[i + 2];
+            if c.is_some_and(|c: char| low <= c && c <= high) {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if c == Some(low) {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+pub struct IgnoreRules {
+    root: PathBuf,
+    rules: Vec<Rule>,
+    loaded: HashSet<String>,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path, exclude_path: &Path) -> Self {
+        let mut ignore: IgnoreRules = IgnoreRules {
+            root: root.to_path_buf(),
+            rules: Vec::new(),
+            loaded: HashSet::new(),
+        };
+
+        let source: String = exclude_path
+            .strip_prefix(root)
+            .unwrap_or(exclude_path)
+            .to_string_lossy()
+            .replace("\\", "/");
+        ignore.load_file(exclude_path, &source, "");
+        ignore.load_dir("");
+        ignore
+    }
+
+    fn load_file(&mut self, path: &Path, source: &str, base: &str) {
+        let Ok(content) = fs::read_to_string(path) else {
+            return;
+        };
+        for (number, line) in content.lines().enumerate() {
+            if let Some(rule) = Rule::parse(source, base, number + 1, line) {
+                self.rules.push(rule);
+            }
+        }
+    }
+
+    pub fn load_dir(&mut self, dir: &str) {
+        if !self.loaded.insert(dir.to_string()) {
+            return;
+        }
+        let source: String = if dir.is_empty() {
+            IGNORE_FILE.to_string()
+        } else {
+            format!("{}/{}", dir, IGNORE_FILE)
+        };
+        let path: PathBuf = self.root.join(&source);
+        self.load_file(&path, &source, dir);
+    }
+
+    pub fn matching_rule(&self, path: &str, is_dir: bool) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule: &&Rule| rule.matches(path, is_dir))
+    }
+
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.matching_rule(path, is_dir)
+            .is_some_and(|rule: &Rule| !rule.negated)
+    }
+
+    pub fn check(&mut self, path: &str, is_dir: bool) -> Option<Rule> {
+        if let Some((parents, _)) = path.rsplit_once('/') {
+            let mut dir: String = String::new();
+            for part in parents.split('/') {
+                if !dir.is_empty() {
+                    dir.push('/');
+                }
+                dir.push_str(part);
+                if self.is_ignored(&dir, true) {
+                    return self.matching_rule(&dir, true).cloned();
+                }
+                self.load_dir(&dir);
+            }
+        }
+
+        self.matching_rule(path, is_dir).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn glob(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob_match(&pattern, &text)
+    }
+
+    #[test]
+    fn test_glob_wildcards() {
+        assert!(glob("*.log", "debug.log"));
+        assert!(!glob("*.log", "logs/debug.log"));
+        assert!(glob("**/*.log", "logs/debug.log"));
+        assert!(glob("**/*.log", "debug.log"));
+        assert!(glob("a/**/b", "a/b"));
+        assert!(glob("a/**/b", "a/x/y/b"));
+        assert!(glob("target/**", "target/debug/nub"));
+        assert!(!glob("target/**", "target"));
+        assert!(glob("file?.txt", "file1.txt"));
+        assert!(glob("file[0-9].txt", "file7.txt"));
+        assert!(!glob("file[!0-9].txt", "file7.txt"));
+    }
+
+    #[test]
+    fn test_rules_with_negation_and_nesting() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let root: &Path = temp.path();
+        fs::write(
+            root.join(".nubignore"),
+            "*.log\n!keep.log\nbuild/\n/todo.txt\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/.nubignore"), "*.tmp\n!important.log\n").unwrap();
+        fs::write(root.join("exclude"), "secret.env\n").unwrap();
+
+        let mut ignore: IgnoreRules = IgnoreRules::new(root, &root.join("exclude"));
+        ignore.load_dir("docs");
+
+        assert!(ignore.is_ignored("debug.log", false));
+        assert!(ignore.is_ignored("src/debug.log", false));
+        assert!(!ignore.is_ignored("keep.log", false));
+        assert!(ignore.is_ignored("build", true));
+        assert!(!ignore.is_ignored("build", false));
+        assert!(ignore.is_ignored("todo.txt", false));
+        assert!(!ignore.is_ignored("docs/todo.txt", false));
+        assert!(ignore.is_ignored("docs/draft.tmp", false));
+        assert!(!ignore.is_ignored("draft.tmp", false));
+        assert!(!ignore.is_ignored("docs/important.log", false));
+        assert!(ignore.is_ignored("config/secret.env", false));
+
+        let rule: Rule = ignore.check("build/out/app", false).unwrap();
+        assert_eq!((rule.source.as_str(), rule.line), (".nubignore", 3));
+    }
+}
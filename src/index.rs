@@ -0,0 +1,480 @@
+use crate::error::NubError;
+use crate::lockfile::LockFile;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const INDEX_MAGIC: &[u8; 4] = b"NUBI";
+const INDEX_VERSION: u32 = 1;
+const CHECKSUM_LEN: usize = 32;
+
+const FLAG_STAGED: u8 = 1;
+const FLAG_DELETED: u8 = 2;
+const FLAG_STAT: u8 = 4;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexRecord {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deleted: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    pub ctime: (i64, u32),
+    pub mtime: (i64, u32),
+    pub ino: u64,
+    pub mode: u32,
+    pub size: u64,
+}
+
+impl FileStat {
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        FileStat {
+            ctime: (metadata.ctime(), metadata.ctime_nsec() as u32),
+            mtime: (metadata.mtime(), metadata.mtime_nsec() as u32),
+            ino: metadata.ino(),
+            mode: metadata.mode(),
+            size: metadata.size(),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let mtime: (i64, u32) = metadata
+            .modified()
+            .ok()
+            .and_then(|time: SystemTime| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| (since.as_secs() as i64, since.subsec_nanos()))
+            .unwrap_or_default();
+
+        FileStat {
+            ctime: mtime,
+            mtime,
+            ino: 0,
+            mode: 0,
+            size: metadata.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedEntry {
+    hash: String,
+    stat: FileStat,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Index {
+    entries: BTreeMap<String, Option<String>>,
+    cache: BTreeMap<String, CachedEntry>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.pos + len > self.data.len() {
+            return Err(NubError::CorruptIndex("unexpected end of file".to_string()).into());
+        }
+        let bytes: &[u8] = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| NubError::CorruptIndex("invalid UTF-8 in entry".to_string()).into())
+    }
+}
+
+fn write_string_u8(out: &mut Vec<u8>, value: &str) -> Result<()> {
+    let len: u8 =
+        u8::try_from(value.len()).map_err(|_| NubError::IndexEntryTooLong(value.to_string()))?;
+    out.push(len);
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn write_time(out: &mut Vec<u8>, (secs, nanos): (i64, u32)) {
+    out.extend_from_slice(&secs.to_be_bytes());
+    out.extend_from_slice(&nanos.to_be_bytes());
+}
+
+impl Index {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Index::default());
+        }
+
+        let data: Vec<u8> = fs::read(path)?;
+        if data.starts_with(INDEX_MAGIC) {
+            return Index::decode(&data);
+        }
+
+        let records: Vec<IndexRecord> = serde_json::from_slice(&data).map_err(NubError::from)?;
+
+        let mut index: Index = Index::default();
+        for record in records {
+            let path: String = record.path.replace("\\", "/");
+            match record.hash {
+                Some(hash) if !record.deleted => index.stage(path, hash),
+                _ => index.stage_deletion(path),
+            }
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.write_to(LockFile::acquire(path)?)
+    }
+
+    pub fn write_to(&self, mut lock: LockFile) -> Result<()> {
+        let now: i64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs() as i64)
+            .unwrap_or(i64::MAX);
+        lock.write(&self.encode(now)?)?;
+        lock.commit()
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < INDEX_MAGIC.len() + CHECKSUM_LEN {
+            return Err(NubError::CorruptIndex("file too short".to_string()).into());
+        }
+        let (body, checksum) = data.split_at(data.len() - CHECKSUM_LEN);
+        if Sha256::digest(body)[..] != *checksum {
+            return Err(NubError::CorruptIndex("checksum mismatch".to_string()).into());
+        }
+
+        let mut reader: Reader = Reader {
+            data: body,
+            pos: INDEX_MAGIC.len(),
+        };
+        let version: u32 = reader.u32()?;
+        if version != INDEX_VERSION {
+            return Err(NubError::CorruptIndex(format!("unsupported version {}", version)).into());
+        }
+
+        let mut index: Index = Index::default();
+        let count: u32 = reader.u32()?;
+        for _ in 0..count {
+            let flags: u8 = reader.u8()?;
+            let path_len: usize = reader.u16()? as usize;
+            let path: String = reader.string(path_len)?;
+
+            if flags & FLAG_DELETED != 0 {
+                index.stage_deletion(path.clone());
+            } else if flags & FLAG_STAGED != 0 {
+                let hash_len: usize = reader.u8()? as usize;
+                let hash: String = reader.string(hash_len)?;
+                index.stage(path.clone(), hash);
+            }
+
+            if flags & FLAG_STAT != 0 {
+                let hash_len: usize = reader.u8()? as usize;
+                let hash: String = reader.string(hash_len)?;
+                let stat: FileStat = FileStat {
+                    ctime: (reader.i64()?, reader.u32()?),
+                    mtime: (reader.i64()?, reader.u32()?),
+                    ino: reader.u64()?,
+                    mode: reader.u32()?,
+                    size: reader.u64()?,
+                };
+                index.cache.insert(path, CachedEntry { hash, stat });
+            }
+        }
+
+        if reader.pos != body.len() {
+            return Err(NubError::CorruptIndex("trailing data".to_string()).into());
+        }
+        Ok(index)
+    }
+
+    fn encode(&self, now: i64) -> Result<Vec<u8>> {
+        let mut paths: Vec<&String> = self.entries.keys().collect();
+        paths.extend(
+            self.cache
+                .iter()
+                .filter(|(path, cached)| {
+                    !self.entries.contains_key(*path) && cached.stat.mtime.0 < now
+                })
+                .map(|(path, _)| path),
+        );
+        paths.sort();
+
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(INDEX_MAGIC);
+        out.extend_from_slice(&INDEX_VERSION.to_be_bytes());
+        out.extend_from_slice(&(paths.len() as u32).to_be_bytes());
+
+        for path in paths {
+            let staged: Option<&Option<String>> = self.entries.get(path);
+            let cached: Option<&CachedEntry> = self
+                .cache
+                .get(path)
+                .filter(|cached: &&CachedEntry| cached.stat.mtime.0 < now);
+
+            let mut flags: u8 = 0;
+            match staged {
+                Some(Some(_)) => flags |= FLAG_STAGED,
+                Some(None) => flags |= FLAG_STAGED | FLAG_DELETED,
+                None => {}
+            }
+            if cached.is_some() {
+                flags |= FLAG_STAT;
+            }
+
+            out.push(flags);
+            let path_len: u16 = u16::try_from(path.len())
+                .map_err(|_| NubError::IndexEntryTooLong(path.to_string()))?;
+            out.extend_from_slice(&path_len.to_be_bytes());
+            out.extend_from_slice(path.as_bytes());
+            if let Some(Some(hash)) = staged {
+                write_string_u8(&mut out, hash)?;
+            }
+            if let Some(cached) = cached {
+                write_string_u8(&mut out, &cached.hash)?;
+                write_time(&mut out, cached.stat.ctime);
+                write_time(&mut out, cached.stat.mtime);
+                out.extend_from_slice(&cached.stat.ino.to_be_bytes());
+                out.extend_from_slice(&cached.stat.mode.to_be_bytes());
+                out.extend_from_slice(&cached.stat.size.to_be_bytes());
+            }
+        }
+
+        let checksum = Sha256::digest(&out);
+        out.extend_from_slice(&checksum);
+        Ok(out)
+    }
+
+    pub fn stage(&mut self, path: String, hash: String) {
+        self.entries.insert(path, Some(hash));
+    }
+
+    pub fn stage_deletion(&mut self, path: String) {
+        self.entries.insert(path, None);
+    }
+
+    pub fn unstage(&mut self, path: &str) {
+        self.entries.remove(path);
+    }
+
+    pub fn reset_entry(&mut self, path: &str, wanted: Option<&String>, committed: Option<&String>) {
+        match wanted {
+            _ if wanted == committed => self.unstage(path),
+            Some(hash) => self.stage(path.to_string(), hash.clone()),
+            None => self.stage_deletion(path.to_string()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn get(&self, path: &str) -> Option<Option<&String>> {
+        self.entries.get(path).map(Option::as_ref)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, Option<&String>)> {
+        self.entries
+            .iter()
+            .map(|(path, hash)| (path, hash.as_ref()))
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&String) -> bool) {
+        self.entries.retain(|path, _| keep(path));
+    }
+
+    pub fn apply_to(&self, files: &mut HashMap<String, String>) {
+        for (path, hash) in &self.entries {
+            match hash {
+                Some(hash) => {
+                    files.insert(path.clone(), hash.clone());
+                }
+                None => {
+                    files.remove(path);
+                }
+            }
+        }
+    }
+
+    pub fn cached_hash(&self, path: &str, stat: &FileStat) -> Option<&String> {
+        self.cache
+            .get(path)
+            .filter(|cached: &&CachedEntry| &cached.stat == stat)
+            .map(|cached: &CachedEntry| &cached.hash)
+    }
+
+    pub fn record_stat(&mut self, path: String, hash: String, stat: FileStat) {
+        self.cache.insert(path, CachedEntry { hash, stat });
+    }
+
+    pub fn retain_stats(&mut self, mut keep: impl FnMut(&String) -> bool) {
+        self.cache.retain(|path, _| keep(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_roundtrip_keeps_deletions() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let path = temp.path().join("index");
+
+        let mut index: Index = Index::default();
+        index.stage("b.txt".to_string(), "hash-b".to_string());
+        index.stage_deletion("a.txt".to_string());
+        index.save(&path).unwrap();
+
+        let loaded: Index = Index::load(&path).unwrap();
+        assert_eq!(loaded, index);
+        assert_eq!(loaded.get("a.txt"), Some(None));
+    }
+
+    #[test]
+    fn test_reads_legacy_entries() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let path = temp.path().join("index");
+        fs::write(&path, r#"[{"hash": "h1", "path": "src\\main.rs"}]"#).unwrap();
+
+        let index: Index = Index::load(&path).unwrap();
+        assert_eq!(index.get("src/main.rs"), Some(Some(&"h1".to_string())));
+    }
+
+    #[test]
+    fn test_binary_roundtrip_keeps_stat_cache() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let path = temp.path().join("index");
+
+        let stat: FileStat = FileStat {
+            ctime: (100, 5),
+            mtime: (100, 7),
+            ino: 42,
+            mode: 0o100644,
+            size: 12,
+        };
+        let mut index: Index = Index::default();
+        index.stage("a.txt".to_string(), "hash-a".to_string());
+        index.record_stat("a.txt".to_string(), "hash-a".to_string(), stat);
+        index.record_stat("b.txt".to_string(), "hash-b".to_string(), stat);
+        index.save(&path).unwrap();
+
+        let loaded: Index = Index::load(&path).unwrap();
+        assert_eq!(loaded, index);
+        assert_eq!(
+            loaded.cached_hash("b.txt", &stat),
+            Some(&"hash-b".to_string())
+        );
+
+        let touched: FileStat = FileStat {
+            mtime: (101, 0),
+            ..stat
+        };
+        assert_eq!(loaded.cached_hash("b.txt", &touched), None);
+    }
+
+    #[test]
+    fn test_racy_entries_are_not_cached() {
+        let stat: FileStat = FileStat {
+            mtime: (200, 0),
+            ..FileStat::default()
+        };
+        let mut index: Index = Index::default();
+        index.record_stat("racy.txt".to_string(), "hash".to_string(), stat);
+
+        let decoded: Index = Index::decode(&index.encode(200).unwrap()).unwrap();
+        assert_eq!(decoded.cached_hash("racy.txt", &stat), None);
+
+        let decoded: Index = Index::decode(&index.encode(201).unwrap()).unwrap();
+        assert_eq!(
+            decoded.cached_hash("racy.txt", &stat),
+            Some(&"hash".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let mut index: Index = Index::default();
+        index.stage("a.txt".to_string(), "hash-a".to_string());
+        let mut data: Vec<u8> = index.encode(0).unwrap();
+        let last: usize = data.len() - 1;
+        data[last] ^= 0xff;
+
+        assert!(Index::decode(&data).is_err());
+    }
+
+    #[test]
+    fn test_overlong_entries_are_rejected() {
+        let mut index: Index = Index::default();
+        index.stage("a".repeat(usize::from(u16::MAX) + 1), "0".repeat(64));
+        assert!(index.encode(0).is_err());
+
+        let mut index: Index = Index::default();
+        index.stage("a.txt".to_string(), "0".repeat(256));
+        assert!(index.encode(0).is_err());
+    }
+
+    #[test]
+    fn test_apply_to_overlays_tree() {
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("keep".to_string(), "1".to_string());
+        files.insert("gone".to_string(), "2".to_string());
+
+        let mut index: Index = Index::default();
+        index.stage_deletion("gone".to_string());
+        index.stage("new".to_string(), "3".to_string());
+        index.apply_to(&mut files);
+
+        let mut paths: Vec<&String> = files.keys().collect();
+        paths.sort();
+        assert_eq!(paths, vec!["keep", "new"]);
+    }
+}
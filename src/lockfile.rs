@@ -0,0 +1,117 @@
+use crate::error::NubError;
+use anyhow::Result;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const LOCK_SUFFIX: &str = ".lock";
+
+pub struct LockFile {
+    target: PathBuf,
+    lock_path: PathBuf,
+    file: Option<File>,
+    committed: bool,
+}
+
+impl LockFile {
+    pub fn acquire(target: &Path) -> Result<LockFile> {
+        let mut name: OsString = target.file_name().unwrap_or_default().to_os_string();
+        name.push(LOCK_SUFFIX);
+        let lock_path: PathBuf = target.with_file_name(name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file: File = match File::options()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                return Err(NubError::LockHeld(lock_path.display().to_string()).into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(LockFile {
+            target: target.to_path_buf(),
+            lock_path,
+            file: Some(file),
+            committed: false,
+        })
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        fs::rename(&self.lock_path, &self.target)?;
+        self.committed = true;
+        if let Some(parent) = self.target.parent() {
+            sync_dir(parent);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// Flushes a directory entry so a rename into it survives power loss. Not
+/// every platform can open directories, so failures are ignored.
+pub fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut lock: LockFile = LockFile::acquire(path)?;
+    lock.write(data)?;
+    lock.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_exclusive_and_released() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let target: PathBuf = temp.path().join("refs/heads/main");
+
+        let mut lock: LockFile = LockFile::acquire(&target).unwrap();
+        let err: anyhow::Error = LockFile::acquire(&target).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::LockHeld(_))
+        ));
+        lock.write(b"abc").unwrap();
+        assert!(!target.exists());
+        lock.commit().unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "abc");
+        assert!(!temp.path().join("refs/heads/main.lock").exists());
+
+        let mut abandoned: LockFile = LockFile::acquire(&target).unwrap();
+        abandoned.write(b"partial").unwrap();
+        drop(abandoned);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "abc");
+        write_atomic(&target, b"def").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "def");
+    }
+}
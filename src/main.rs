@@ -1,14 +1,39 @@
 mod cli;
 mod commands;
+mod config;
+mod diff;
 mod error;
+mod fsck;
+mod ignore;
+mod index;
+mod lockfile;
+mod migrate;
+mod object_store;
 mod objects;
+mod pack;
+mod prune;
+mod reflog;
 mod repository;
+mod worktree;
 
 use anyhow::Result;
 use clap::Parser;
 use cli::Cli;
+use config::Config;
+use repository::Repository;
+use std::path::PathBuf;
 
 fn main() -> Result<()> {
     let cli: Cli = Cli::parse();
+
+    let local: Option<PathBuf> = Repository::find_unchecked()
+        .ok()
+        .map(|repo: Repository| repo.config_path());
+    if let Ok(config) = Config::load(local.as_deref())
+        && let Ok(Some(false)) = config.get_bool("color.ui")
+    {
+        colored::control::set_override(false);
+    }
+
     cli.execute()
 }
@@ -0,0 +1,508 @@
+use crate::error::NubError;
+use crate::index::Index;
+use crate::lockfile;
+use crate::object_store::ObjectStore;
+use crate::objects::tree::EntryType;
+use crate::objects::{Commit, ObjectType, Tree, encode_object, hash_object};
+use crate::repository::{self, Head, Repository};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const MIGRATING_DIR: &str = "objects.migrating";
+const RETIRED_DIR: &str = "objects.legacy";
+const JOURNAL_FILE: &str = "MIGRATE_STATE";
+const HEAD_REF: &str = "HEAD";
+const REASON: &str = "migrate: upgrade object layout";
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub commits: usize,
+    pub trees: usize,
+    pub blobs: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RefRewrite {
+    name: String,
+    old: String,
+    new: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Journal {
+    report: MigrationReport,
+    refs: Vec<RefRewrite>,
+    merge_head: Option<String>,
+    index: Vec<(String, Option<String>)>,
+}
+
+struct Migrator<'a> {
+    repo: &'a Repository,
+    target: ObjectStore,
+    migrated: HashMap<(ObjectType, String), String>,
+    report: MigrationReport,
+}
+
+impl Migrator<'_> {
+    fn store(&mut self, kind: ObjectType, body: &[u8]) -> Result<String> {
+        let hash: String = hash_object(kind, body);
+        self.target.write(&hash, &encode_object(kind, body))?;
+        Ok(hash)
+    }
+
+    fn read_legacy(&self, dir: &Path, hash: &str) -> Result<Vec<u8>> {
+        let path: PathBuf = dir.join(hash);
+        if !path.is_file() {
+            return Err(NubError::ObjectNotFound(hash.to_string()).into());
+        }
+        Ok(fs::read(path)?)
+    }
+
+    fn blob(&mut self, old: &str) -> Result<String> {
+        let key: (ObjectType, String) = (ObjectType::Blob, old.to_string());
+        if let Some(new) = self.migrated.get(&key) {
+            return Ok(new.clone());
+        }
+
+        let content: Vec<u8> = self.read_legacy(&self.repo.objects_dir(), old)?;
+        let new: String = self.store(ObjectType::Blob, &content)?;
+        self.report.blobs += 1;
+        self.migrated.insert(key, new.clone());
+        Ok(new)
+    }
+
+    fn tree(&mut self, old: &str) -> Result<String> {
+        let key: (ObjectType, String) = (ObjectType::Tree, old.to_string());
+        if let Some(new) = self.migrated.get(&key) {
+            return Ok(new.clone());
+        }
+
+        let data: Vec<u8> = self.read_legacy(&self.repo.objects_dir(), old)?;
+        let mut tree: Tree =
+            serde_json::from_slice(&data).map_err(|_| NubError::CorruptObject(old.to_string()))?;
+
+        let new: String = if tree.entries.iter().any(|entry| entry.name.contains('/')) {
+            // Early trees stored whole paths as entry names; rebuild them nested.
+            let mut files: HashMap<String, String> = HashMap::new();
+            self.flat_files(&tree, "", &mut files)?;
+            repository::write_tree_from_files(&self.target, &files)?
+        } else {
+            for entry in &mut tree.entries {
+                entry.hash = match entry.entry_type {
+                    EntryType::Blob => self.blob(&entry.hash)?,
+                    EntryType::Tree => self.tree(&entry.hash)?,
+                };
+            }
+            self.store(ObjectType::Tree, tree.canonical_json()?.as_bytes())?
+        };
+        self.report.trees += 1;
+        self.migrated.insert(key, new.clone());
+        Ok(new)
+    }
+
+    fn flat_files(
+        &mut self,
+        tree: &Tree,
+        prefix: &str,
+        files: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        for entry in &tree.entries {
+            let path: String = format!("{}{}", prefix, entry.name);
+            match entry.entry_type {
+                EntryType::Blob => {
+                    files.insert(path, self.blob(&entry.hash)?);
+                }
+                EntryType::Tree => {
+                    let data: Vec<u8> = self.read_legacy(&self.repo.objects_dir(), &entry.hash)?;
+                    let subtree: Tree = serde_json::from_slice(&data)
+                        .map_err(|_| NubError::CorruptObject(entry.hash.clone()))?;
+                    self.flat_files(&subtree, &format!("{}/", path), files)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self, old: &str) -> Result<String> {
+        let mut pending: Vec<String> = vec![old.to_string()];
+        while let Some(hash) = pending.last().cloned() {
+            let key: (ObjectType, String) = (ObjectType::Commit, hash.clone());
+            if self.migrated.contains_key(&key) {
+                pending.pop();
+                continue;
+            }
+
+            let data: Vec<u8> = self.read_legacy(&self.repo.legacy_commits_dir(), &hash)?;
+            let mut commit: Commit =
+                serde_json::from_slice(&data).map_err(|_| NubError::CorruptObject(hash.clone()))?;
+
+            let missing: Vec<String> = commit
+                .parents
+                .iter()
+                .filter(|parent: &&String| {
+                    !self
+                        .migrated
+                        .contains_key(&(ObjectType::Commit, (*parent).clone()))
+                })
+                .cloned()
+                .collect();
+            if !missing.is_empty() {
+                pending.extend(missing);
+                continue;
+            }
+
+            commit.tree = self.tree(&commit.tree)?;
+            commit.parents = commit
+                .parents
+                .iter()
+                .map(|parent: &String| self.migrated[&(ObjectType::Commit, parent.clone())].clone())
+                .collect();
+
+            let body: String = serde_json::to_string_pretty(&commit)?;
+            let new: String = self.store(ObjectType::Commit, body.as_bytes())?;
+            self.report.commits += 1;
+            self.migrated.insert(key, new);
+            pending.pop();
+        }
+        Ok(self.migrated[&(ObjectType::Commit, old.to_string())].clone())
+    }
+}
+
+/// Returns whether an earlier `nub migrate` stopped after recording its plan.
+pub fn is_pending(repo: &Repository) -> bool {
+    journal_path(repo).exists()
+}
+
+fn journal_path(repo: &Repository) -> PathBuf {
+    repo.nub_dir.join(JOURNAL_FILE)
+}
+
+/// Copies every reachable legacy object into `objects.migrating` and works
+/// out the new value of each ref. Nothing outside `objects.migrating` is
+/// modified, so this phase can simply be run again after a crash.
+fn copy_objects(repo: &Repository) -> Result<Journal> {
+    let target: PathBuf = repo.nub_dir.join(MIGRATING_DIR);
+    let retired: PathBuf = repo.nub_dir.join(RETIRED_DIR);
+    if retired.exists() && !target.exists() {
+        // A swap finished without a journal: put the legacy store back first.
+        fs::rename(repo.objects_dir(), &target)?;
+        fs::rename(&retired, repo.objects_dir())?;
+    }
+    // Objects already in the new store were written by rename and are
+    // complete, so an interrupted copy is resumed rather than thrown away.
+    fs::create_dir_all(&target)?;
+
+    let mut migrator: Migrator = Migrator {
+        repo,
+        target: ObjectStore::new(target),
+        migrated: HashMap::new(),
+        report: MigrationReport::default(),
+    };
+
+    let mut legacy_commits: Vec<String> = Vec::new();
+    for entry in fs::read_dir(repo.legacy_commits_dir())? {
+        legacy_commits.push(entry?.file_name().to_string_lossy().to_string());
+    }
+    legacy_commits.sort();
+    for hash in &legacy_commits {
+        migrator.commit(hash)?;
+    }
+
+    let mut targets: Vec<(String, String)> = Vec::new();
+    for name in repo.list_branches()? {
+        if let Some(hash) = repo.read_branch(&name)? {
+            targets.push((format!("refs/heads/{}", name), hash));
+        }
+    }
+    if let Head::Detached(hash) = repo.read_head()? {
+        targets.push((HEAD_REF.to_string(), hash));
+    }
+    let mut refs: Vec<RefRewrite> = Vec::new();
+    for (name, old) in targets {
+        // Left over from a run that moved this ref before journaling existed.
+        if !legacy_commits.contains(&old) && migrator.target.contains(&old) {
+            continue;
+        }
+        let new: String = migrator.commit(&old)?;
+        refs.push(RefRewrite { name, old, new });
+    }
+    let merge_head: Option<String> = match fs::read_to_string(repo.merge_head_path()) {
+        Ok(hash) => Some(migrator.commit(hash.trim())?),
+        Err(_) => None,
+    };
+
+    let mut index: Vec<(String, Option<String>)> = Vec::new();
+    for (path, hash) in repo.read_index()?.entries() {
+        let migrated: Option<String> = match hash {
+            Some(hash) => Some(migrator.blob(hash)?),
+            None => None,
+        };
+        index.push((path.clone(), migrated));
+    }
+
+    Ok(Journal {
+        report: migrator.report,
+        refs,
+        merge_head,
+        index,
+    })
+}
+
+fn rewrite_refs(repo: &Repository, journal: &Journal) -> Result<()> {
+    for rewrite in &journal.refs {
+        if repo.read_ref(&rewrite.name)?.as_deref() == Some(rewrite.new.as_str()) {
+            continue;
+        }
+        repo.update_ref(&rewrite.name, &rewrite.new, Some(&rewrite.old), REASON)?;
+    }
+    if let Some(hash) = &journal.merge_head {
+        lockfile::write_atomic(&repo.merge_head_path(), hash.as_bytes())?;
+    }
+
+    let mut index: Index = Index::default();
+    for (path, hash) in &journal.index {
+        match hash {
+            Some(hash) => index.stage(path.clone(), hash.clone()),
+            None => index.stage_deletion(path.clone()),
+        }
+    }
+    repo.write_index(&index)
+}
+
+fn swap_directories(repo: &Repository) -> Result<()> {
+    let target: PathBuf = repo.nub_dir.join(MIGRATING_DIR);
+    let retired: PathBuf = repo.nub_dir.join(RETIRED_DIR);
+    if !retired.exists() {
+        fs::rename(repo.objects_dir(), &retired)?;
+    }
+    if target.exists() {
+        fs::rename(&target, repo.objects_dir())?;
+    }
+    Ok(())
+}
+
+/// Upgrades a legacy repository in three steps: copy objects, rewrite refs,
+/// then swap object directories. The plan is journaled before the first ref
+/// changes, and the repository refuses to open until `commits/` is gone, so
+/// rerunning after a crash at any point finishes the same migration.
+pub fn migrate_legacy_layout(repo: &Repository) -> Result<MigrationReport> {
+    let journal: Journal = match fs::read(journal_path(repo)) {
+        Ok(data) => serde_json::from_slice(&data).map_err(NubError::from)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let journal: Journal = copy_objects(repo)?;
+            lockfile::write_atomic(
+                &journal_path(repo),
+                serde_json::to_string_pretty(&journal)?.as_bytes(),
+            )?;
+            journal
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    rewrite_refs(repo, &journal)?;
+    swap_directories(repo)?;
+
+    let retired: PathBuf = repo.nub_dir.join(RETIRED_DIR);
+    if retired.exists() {
+        fs::remove_dir_all(&retired)?;
+    }
+    if repo.legacy_commits_dir().exists() {
+        fs::remove_dir_all(repo.legacy_commits_dir())?;
+    }
+    fs::remove_file(journal_path(repo))?;
+
+    Ok(journal.report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use tempfile::TempDir;
+
+    fn legacy_write(dir: &Path, data: &[u8]) -> String {
+        let hash: String = format!("{:x}", Sha256::digest(data));
+        fs::write(dir.join(&hash), data).unwrap();
+        hash
+    }
+
+    #[test]
+    fn test_migrates_legacy_history() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        fs::create_dir(repo.legacy_commits_dir()).unwrap();
+
+        let objects: PathBuf = repo.objects_dir();
+        let readme: String = legacy_write(&objects, b"hello\n");
+        let main: String = legacy_write(&objects, b"fn main() {}\n");
+        let src: String = legacy_write(
+            &objects,
+            format!(
+                r#"{{"entries":[{{"name":"main.rs","hash":"{}","entry_type":"blob"}}]}}"#,
+                main
+            )
+            .as_bytes(),
+        );
+        let root: String = legacy_write(
+            &objects,
+            format!(
+                r#"{{"entries":[{{"name":"README","hash":"{}","entry_type":"blob"}},{{"name":"src","hash":"{}","entry_type":"tree"}}]}}"#,
+                readme, src
+            )
+            .as_bytes(),
+        );
+
+        let commits: PathBuf = repo.legacy_commits_dir();
+        let first: String = legacy_write(
+            &commits,
+            format!(
+                r#"{{"tree":"{}","parent":null,"author":{{"name":"A","email":"a@nub.local"}},"timestamp":"2024-01-01T00:00:00Z","message":"first"}}"#,
+                root
+            )
+            .as_bytes(),
+        );
+        let second: String = legacy_write(
+            &commits,
+            format!(
+                r#"{{"tree":"{}","parent":"{}","author":{{"name":"A","email":"a@nub.local"}},"timestamp":"2024-01-02T00:00:00Z","message":"second"}}"#,
+                root, first
+            )
+            .as_bytes(),
+        );
+        repo.write_branch("main", &second, "commit").unwrap();
+
+        let mut index: Index = Index::default();
+        let staged: String = legacy_write(&objects, b"staged\n");
+        index.stage("new.txt".to_string(), staged);
+        repo.write_index(&index).unwrap();
+
+        let report: MigrationReport = migrate_legacy_layout(&repo).unwrap();
+        assert_eq!(
+            report,
+            MigrationReport {
+                commits: 2,
+                trees: 2,
+                blobs: 3,
+            }
+        );
+        assert!(!repo.legacy_commits_dir().exists());
+
+        let head: Commit = repo.head_commit().unwrap().unwrap();
+        assert_eq!(head.message, "second");
+        assert_eq!(repo.read_commit(&head.parents[0]).unwrap().message, "first");
+
+        let files: HashMap<String, String> = repo.head_files().unwrap();
+        assert_eq!(repo.read_blob(&files["README"]).unwrap(), b"hello\n");
+        assert_eq!(
+            repo.read_blob(&files["src/main.rs"]).unwrap(),
+            b"fn main() {}\n"
+        );
+
+        let index: Index = repo.read_index().unwrap();
+        let staged: &String = index.get("new.txt").unwrap().unwrap();
+        assert_eq!(repo.read_blob(staged).unwrap(), b"staged\n");
+    }
+
+    #[test]
+    fn test_migrates_flat_legacy_trees() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        fs::create_dir(repo.legacy_commits_dir()).unwrap();
+
+        let objects: PathBuf = repo.objects_dir();
+        let readme: String = legacy_write(&objects, b"hello\n");
+        let main: String = legacy_write(&objects, b"fn main() {}\n");
+        let root: String = legacy_write(
+            &objects,
+            format!(
+                r#"{{"entries":[{{"name":"README","hash":"{}","entry_type":"blob"}},{{"name":"src/main.rs","hash":"{}","entry_type":"blob"}}]}}"#,
+                readme, main
+            )
+            .as_bytes(),
+        );
+        let commit: String = legacy_write(
+            &repo.legacy_commits_dir(),
+            format!(
+                r#"{{"tree":"{}","parent":null,"author":{{"name":"A","email":"a@nub.local"}},"timestamp":"2024-01-01T00:00:00Z","message":"flat"}}"#,
+                root
+            )
+            .as_bytes(),
+        );
+        repo.write_branch("main", &commit, "commit").unwrap();
+
+        migrate_legacy_layout(&repo).unwrap();
+
+        let head: Commit = repo.head_commit().unwrap().unwrap();
+        let tree: Tree = repo.read_tree(&head.tree).unwrap();
+        assert!(tree.entries.iter().all(|entry| !entry.name.contains('/')));
+        let src: &crate::objects::tree::TreeEntry = tree
+            .entries
+            .iter()
+            .find(|entry| entry.name == "src")
+            .unwrap();
+        assert_eq!(src.entry_type, EntryType::Tree);
+
+        let files: HashMap<String, String> = repo.head_files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            repo.read_blob(&files["src/main.rs"]).unwrap(),
+            b"fn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_interrupted_migration_resumes() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        fs::create_dir(repo.legacy_commits_dir()).unwrap();
+
+        let objects: PathBuf = repo.objects_dir();
+        let readme: String = legacy_write(&objects, b"hello\n");
+        let root: String = legacy_write(
+            &objects,
+            format!(
+                r#"{{"entries":[{{"name":"README","hash":"{}","entry_type":"blob"}}]}}"#,
+                readme
+            )
+            .as_bytes(),
+        );
+        let commit: String = legacy_write(
+            &repo.legacy_commits_dir(),
+            format!(
+                r#"{{"tree":"{}","parent":null,"author":{{"name":"A","email":"a@nub.local"}},"timestamp":"2024-01-01T00:00:00Z","message":"first"}}"#,
+                root
+            )
+            .as_bytes(),
+        );
+        repo.write_branch("main", &commit, "commit").unwrap();
+
+        // Stop after copying, before the journal: the copy is simply resumed.
+        copy_objects(&repo).unwrap();
+        // Stop half-way through the directory swap, after refs were rewritten.
+        let journal: Journal = copy_objects(&repo).unwrap();
+        lockfile::write_atomic(
+            &journal_path(&repo),
+            serde_json::to_string(&journal).unwrap().as_bytes(),
+        )
+        .unwrap();
+        rewrite_refs(&repo, &journal).unwrap();
+        fs::rename(repo.objects_dir(), repo.nub_dir.join(RETIRED_DIR)).unwrap();
+        assert!(is_pending(&repo));
+
+        let report: MigrationReport = migrate_legacy_layout(&repo).unwrap();
+        assert_eq!(report.commits, 1);
+        assert!(!is_pending(&repo));
+        assert!(!repo.legacy_commits_dir().exists());
+        assert!(!repo.nub_dir.join(MIGRATING_DIR).exists());
+        assert!(!repo.nub_dir.join(RETIRED_DIR).exists());
+
+        let head: Commit = repo.head_commit().unwrap().unwrap();
+        assert_eq!(head.message, "first");
+        let files: HashMap<String, String> = repo.head_files().unwrap();
+        assert_eq!(repo.read_blob(&files["README"]).unwrap(), b"hello\n");
+    }
+}
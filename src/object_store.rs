@@ -0,0 +1,273 @@
+use crate::lockfile;
+use crate::pack::{self, Pack, PackObject, PackStats};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::cell::OnceCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const PACK_DIR: &str = "pack";
+
+pub struct ObjectStore {
+    dir: PathBuf,
+    packs: OnceCell<Vec<Pack>>,
+}
+
+fn is_hash(name: &str) -> bool {
+    name.len() > 2 && name.chars().all(|c: char| c.is_ascii_hexdigit())
+}
+
+impl ObjectStore {
+    pub fn new(dir: PathBuf) -> Self {
+        ObjectStore {
+            dir,
+            packs: OnceCell::new(),
+        }
+    }
+
+    pub fn pack_dir(&self) -> PathBuf {
+        self.dir.join(PACK_DIR)
+    }
+
+    pub fn packs(&self) -> io::Result<&[Pack]> {
+        if let Some(packs) = self.packs.get() {
+            return Ok(packs);
+        }
+        let pack_dir: PathBuf = self.pack_dir();
+        let mut packs: Vec<Pack> = Vec::new();
+        for name in pack::list_packs(&pack_dir)? {
+            packs.push(Pack::open(&pack_dir, &name)?);
+        }
+        Ok(self.packs.get_or_init(|| packs))
+    }
+
+    pub fn path(&self, hash: &str) -> PathBuf {
+        let (fan_out, rest) = hash.split_at(2.min(hash.len()));
+        self.dir.join(fan_out).join(rest)
+    }
+
+    fn flat_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(hash)
+    }
+
+    pub fn contains_loose(&self, hash: &str) -> bool {
+        self.path(hash).is_file() || self.flat_path(hash).is_file()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.contains_loose(hash)
+            || self
+                .packs()
+                .is_ok_and(|packs: &[Pack]| packs.iter().any(|pack: &Pack| pack.contains(hash)))
+    }
+
+    pub fn read(&self, hash: &str) -> io::Result<Option<Vec<u8>>> {
+        let path: PathBuf = self.path(hash);
+        if path.is_file() {
+            let mut data: Vec<u8> = Vec::new();
+            ZlibDecoder::new(fs::File::open(path)?).read_to_end(&mut data)?;
+            return Ok(Some(data));
+        }
+
+        let flat_path: PathBuf = self.flat_path(hash);
+        if flat_path.is_file() {
+            return Ok(Some(fs::read(flat_path)?));
+        }
+
+        for pack in self.packs()? {
+            if let Some(data) = pack.read(hash)? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn write(&self, hash: &str, data: &[u8]) -> io::Result<()> {
+        let path: PathBuf = self.path(hash);
+        if path.exists() {
+            return self.set_loose_modified(hash, SystemTime::now());
+        }
+        let parent: &Path = path.parent().unwrap_or(&self.dir);
+        let new_fan_out: bool = !parent.is_dir();
+        fs::create_dir_all(parent)?;
+
+        let mut encoder: ZlibEncoder<Vec<u8>> =
+            ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed: Vec<u8> = encoder.finish()?;
+
+        // The object must be on disk before its name is, or a ref written
+        // afterwards could point at a truncated file after a crash.
+        let temp_path: PathBuf = parent.join(format!("tmp_{}", hash));
+        let mut file: fs::File = fs::File::create(&temp_path)?;
+        file.write_all(&compressed)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, &path)?;
+        lockfile::sync_dir(parent);
+        if new_fan_out {
+            lockfile::sync_dir(&self.dir);
+        }
+        Ok(())
+    }
+
+    pub fn find_prefix(&self, prefix: &str) -> io::Result<Vec<String>> {
+        Ok(self
+            .hashes()?
+            .into_iter()
+            .filter(|hash: &String| hash.starts_with(prefix))
+            .collect())
+    }
+
+    pub fn hashes(&self) -> io::Result<Vec<String>> {
+        let mut hashes: BTreeSet<String> = self.loose_hashes()?.into_iter().collect();
+        for pack in self.packs()? {
+            hashes.extend(pack.hashes());
+        }
+        Ok(hashes.into_iter().collect())
+    }
+
+    pub fn loose_hashes(&self) -> io::Result<Vec<String>> {
+        let mut hashes: Vec<String> = Vec::new();
+        if !self.dir.is_dir() {
+            return Ok(hashes);
+        }
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry: fs::DirEntry = entry?;
+            let name: String = entry.file_name().to_string_lossy().to_string();
+            let file_type: fs::FileType = entry.file_type()?;
+
+            if file_type.is_file() && is_hash(&name) {
+                hashes.push(name);
+            } else if file_type.is_dir() && name.len() == 2 && is_hash(&format!("{}0", name)) {
+                for object in fs::read_dir(entry.path())? {
+                    let rest: String = object?.file_name().to_string_lossy().to_string();
+                    if is_hash(&rest) {
+                        hashes.push(format!("{}{}", name, rest));
+                    }
+                }
+            }
+        }
+        hashes.sort();
+        hashes.dedup();
+        Ok(hashes)
+    }
+
+    pub fn loose_modified(&self, hash: &str) -> io::Result<SystemTime> {
+        let path: PathBuf = self.path(hash);
+        let path: PathBuf = if path.is_file() {
+            path
+        } else {
+            self.flat_path(hash)
+        };
+        fs::metadata(path)?.modified()
+    }
+
+    pub fn set_loose_modified(&self, hash: &str, time: SystemTime) -> io::Result<()> {
+        fs::File::options()
+            .append(true)
+            .open(self.path(hash))?
+            .set_modified(time)
+    }
+
+    pub fn pack_modified(&self, name: &str) -> io::Result<SystemTime> {
+        fs::metadata(self.pack_dir().join(format!("{}.pack", name)))?.modified()
+    }
+
+    pub fn remove_loose(&self, hash: &str) -> io::Result<()> {
+        for path in [self.path(hash), self.flat_path(hash)] {
+            if path.is_file() {
+                fs::remove_file(&path)?;
+                if let Some(parent) = path.parent()
+                    && parent != self.dir
+                {
+                    let _ = fs::remove_dir(parent);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_pack(&mut self, objects: &[PackObject]) -> io::Result<(String, PackStats)> {
+        self.packs.take();
+        pack::write_pack(&self.pack_dir(), objects)
+    }
+
+    pub fn remove_pack(&mut self, name: &str) -> io::Result<()> {
+        self.packs.take();
+        for extension in ["pack", "idx"] {
+            let path: PathBuf = self.pack_dir().join(format!("{}.{}", name, extension));
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flat_hashes(&self) -> io::Result<Vec<String>> {
+        let mut hashes: Vec<String> = Vec::new();
+        if !self.dir.is_dir() {
+            return Ok(hashes);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let entry: fs::DirEntry = entry?;
+            let name: String = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_file() && is_hash(&name) {
+                hashes.push(name);
+            }
+        }
+        hashes.sort();
+        Ok(hashes)
+    }
+
+    pub fn shard_flat_objects(&self) -> io::Result<usize> {
+        let flat: Vec<String> = self.flat_hashes()?;
+        for hash in &flat {
+            let data: Vec<u8> = fs::read(self.flat_path(hash))?;
+            self.write(hash, &data)?;
+            fs::remove_file(self.flat_path(hash))?;
+        }
+        Ok(flat.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const HASH: &str = "abcdef0123456789abcdef0123456789abcdef0123456789abcdef0123456789";
+
+    #[test]
+    fn test_objects_are_compressed_and_sharded() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let store: ObjectStore = ObjectStore::new(temp.path().to_path_buf());
+        let data: Vec<u8> = b"blob 600\0".iter().chain(&[b'x'; 600]).copied().collect();
+
+        store.write(HASH, &data).unwrap();
+
+        let path: PathBuf = temp.path().join("ab").join(&HASH[2..]);
+        assert!(path.is_file());
+        assert!(fs::metadata(&path).unwrap().len() < data.len() as u64);
+        assert_eq!(store.read(HASH).unwrap(), Some(data));
+        assert_eq!(store.find_prefix("abcd").unwrap(), vec![HASH.to_string()]);
+        assert_eq!(store.read("ab00").unwrap(), None);
+    }
+
+    #[test]
+    fn test_reads_and_shards_flat_objects() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let store: ObjectStore = ObjectStore::new(temp.path().to_path_buf());
+        fs::write(temp.path().join(HASH), b"blob 2\0hi").unwrap();
+
+        assert_eq!(store.read(HASH).unwrap(), Some(b"blob 2\0hi".to_vec()));
+        assert_eq!(store.shard_flat_objects().unwrap(), 1);
+        assert!(!temp.path().join(HASH).exists());
+        assert_eq!(store.read(HASH).unwrap(), Some(b"blob 2\0hi".to_vec()));
+        assert_eq!(store.hashes().unwrap(), vec![HASH.to_string()]);
+    }
+}
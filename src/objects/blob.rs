@@ -1,4 +1,4 @@
-use sha2::{Digest, Sha256};
+use super::{ObjectType, hash_object};
 
 pub struct Blob {
     pub content: Vec<u8>,
@@ -7,13 +7,7 @@ pub struct Blob {
 
 impl Blob {
     pub fn new(content: Vec<u8>) -> Self {
-        let hash: String = Self::calculate_hash(&content);
+        let hash: String = hash_object(ObjectType::Blob, &content);
         Blob { content, hash }
     }
-
-    fn calculate_hash(content: &[u8]) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(content);
-        format!("{:x}", hasher.finalize())
-    }
 }
@@ -1,29 +1,91 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Commit {
     pub tree: String,
-    pub parent: Option<String>,
+    #[serde(default, alias = "parent", deserialize_with = "deserialize_parents")]
+    pub parents: Vec<String>,
     pub author: Author,
     pub timestamp: DateTime<Utc>,
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Author {
     pub name: String,
     pub email: String,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Parents {
+    One(String),
+    Many(Vec<String>),
+}
+
+fn deserialize_parents<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Parents>::deserialize(deserializer)? {
+        None => Vec::new(),
+        Some(Parents::One(parent)) => vec![parent],
+        Some(Parents::Many(parents)) => parents,
+    })
+}
+
 impl Commit {
-    pub fn new(tree: String, parent: Option<String>, author: Author, message: String) -> Self {
+    pub fn new(tree: String, parents: Vec<String>, author: Author, message: String) -> Self {
         Commit {
             tree,
-            parent,
+            parents,
             author,
             timestamp: Utc::now(),
             message,
         }
     }
+
+    pub fn is_merge(&self) -> bool {
+        self.parents.len() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_legacy_single_parent() {
+        let json: &str = r#"{
+            "tree": "t",
+            "parent": "p1",
+            "author": {"name": "a", "email": "e"},
+            "timestamp": "2024-01-01T00:00:00Z",
+            "message": "m"
+        }"#;
+        let commit: Commit = serde_json::from_str(json).unwrap();
+        assert_eq!(commit.parents, vec!["p1".to_string()]);
+
+        let root: Commit = serde_json::from_str(&json.replace("\"p1\"", "null")).unwrap();
+        assert!(root.parents.is_empty());
+    }
+
+    #[test]
+    fn test_roundtrip_multiple_parents() {
+        let author: Author = Author {
+            name: "a".to_string(),
+            email: "e".to_string(),
+        };
+        let commit: Commit = Commit::new(
+            "t".to_string(),
+            vec!["p1".to_string(), "p2".to_string()],
+            author,
+            "merge".to_string(),
+        );
+        let json: String = serde_json::to_string(&commit).unwrap();
+        let parsed: Commit = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.parents, commit.parents);
+        assert!(parsed.is_merge());
+    }
 }
@@ -1,7 +1,144 @@
 pub mod blob;
 pub mod commit;
+pub mod tag;
 pub mod tree;
 
 pub use blob::Blob;
 pub use commit::Commit;
+pub use tag::Tag;
 pub use tree::Tree;
+
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectType {
+    Blob,
+    Tree,
+    Commit,
+    Tag,
+}
+
+impl ObjectType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectType::Blob => "blob",
+            ObjectType::Tree => "tree",
+            ObjectType::Commit => "commit",
+            ObjectType::Tag => "tag",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "blob" => Some(ObjectType::Blob),
+            "tree" => Some(ObjectType::Tree),
+            "commit" => Some(ObjectType::Commit),
+            "tag" => Some(ObjectType::Tag),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+fn header(kind: ObjectType, size: usize) -> String {
+    format!("{} {}\0", kind, size)
+}
+
+pub fn encode_object(kind: ObjectType, body: &[u8]) -> Vec<u8> {
+    let mut data: Vec<u8> = header(kind, body.len()).into_bytes();
+    data.extend_from_slice(body);
+    data
+}
+
+pub fn decode_object(data: &[u8]) -> Option<(ObjectType, &[u8])> {
+    let nul: usize = data.iter().position(|b: &u8| *b == 0)?;
+    let header: &str = std::str::from_utf8(&data[..nul]).ok()?;
+    let (kind, size) = header.split_once(' ')?;
+    let kind: ObjectType = ObjectType::parse(kind)?;
+    let body: &[u8] = &data[nul + 1..];
+    if size.parse::<usize>().ok()? != body.len() {
+        return None;
+    }
+    Some((kind, body))
+}
+
+/// Abbreviates a hash for display, leaving short or corrupt hashes intact.
+pub fn short_hash(hash: &str) -> &str {
+    hash.get(..8).unwrap_or(hash)
+}
+
+pub fn hash_object(kind: ObjectType, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(header(kind, body.len()).as_bytes());
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+pub enum Object {
+    Blob(Blob),
+    Tree(Tree),
+    Commit(Commit),
+    Tag(Tag),
+}
+
+impl Object {
+    pub fn kind(&self) -> ObjectType {
+        match self {
+            Object::Blob(_) => ObjectType::Blob,
+            Object::Tree(_) => ObjectType::Tree,
+            Object::Commit(_) => ObjectType::Commit,
+            Object::Tag(_) => ObjectType::Tag,
+        }
+    }
+
+    pub fn encode(&self) -> serde_json::Result<Vec<u8>> {
+        match self {
+            Object::Blob(blob) => Ok(blob.content.clone()),
+            Object::Tree(tree) => Ok(tree.canonical_json()?.into_bytes()),
+            Object::Commit(commit) => Ok(serde_json::to_string_pretty(commit)?.into_bytes()),
+            Object::Tag(tag) => Ok(serde_json::to_string_pretty(tag)?.into_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_is_part_of_hash() {
+        let data: Vec<u8> = encode_object(ObjectType::Blob, b"hello");
+        assert_eq!(data, b"blob 5\0hello");
+        assert_eq!(
+            decode_object(&data),
+            Some((ObjectType::Blob, &b"hello"[..]))
+        );
+        assert_ne!(
+            hash_object(ObjectType::Blob, b"{}"),
+            hash_object(ObjectType::Tree, b"{}")
+        );
+        assert_eq!(
+            Blob::new(b"hello".to_vec()).hash,
+            hash_object(ObjectType::Blob, b"hello")
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        assert_eq!(decode_object(b"blob 6\0hello"), None);
+        assert_eq!(decode_object(b"sock 5\0hello"), None);
+        assert_eq!(decode_object(b"hello"), None);
+    }
+
+    #[test]
+    fn test_short_hash_keeps_short_input() {
+        assert_eq!(short_hash("abc"), "abc");
+        assert_eq!(short_hash("0123456789abcdef"), "01234567");
+    }
+}
@@ -0,0 +1,33 @@
+use super::commit::Author;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub object: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub tag: String,
+    pub tagger: Author,
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
+impl Tag {
+    pub fn new(
+        object: String,
+        object_type: String,
+        tag: String,
+        tagger: Author,
+        message: String,
+    ) -> Self {
+        Tag {
+            object,
+            object_type,
+            tag,
+            tagger,
+            timestamp: Utc::now(),
+            message,
+        }
+    }
+}
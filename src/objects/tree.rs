@@ -1,20 +1,27 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TreeEntry {
     pub name: String,
     pub hash: String,
     pub entry_type: EntryType,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum EntryType {
     Blob,
     Tree,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeChange {
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tree {
     pub entries: Vec<TreeEntry>,
 }
@@ -27,10 +34,66 @@ impl Tree {
     }
 
     pub fn add_entry(&mut self, name: String, hash: String, entry_type: EntryType) {
-        self.entries.push(TreeEntry {
-            name,
-            hash,
-            entry_type,
-        });
+        let position: usize = self
+            .entries
+            .partition_point(|e: &TreeEntry| e.name.as_bytes() < name.as_bytes());
+        self.entries.insert(
+            position,
+            TreeEntry {
+                name,
+                hash,
+                entry_type,
+            },
+        );
+    }
+
+    pub fn canonical_json(&self) -> serde_json::Result<String> {
+        let mut entries: Vec<TreeEntry> = self.entries.clone();
+        entries.sort_by(|a: &TreeEntry, b: &TreeEntry| a.name.as_bytes().cmp(b.name.as_bytes()));
+        serde_json::to_string(&Tree { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(order: &[usize]) -> Tree {
+        let entries: [(&str, &str, EntryType); 4] = [
+            ("b.txt", "1", EntryType::Blob),
+            ("a.txt", "2", EntryType::Blob),
+            ("src", "3", EntryType::Tree),
+            ("Z.md", "4", EntryType::Blob),
+        ];
+        let mut tree: Tree = Tree::new();
+        for &i in order {
+            let (name, hash, entry_type) = entries[i];
+            tree.add_entry(name.to_string(), hash.to_string(), entry_type);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_entries_are_sorted_by_name_bytes() {
+        let tree: Tree = sample(&[0, 1, 2, 3]);
+        let names: Vec<&str> = tree.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Z.md", "a.txt", "b.txt", "src"]);
+    }
+
+    #[test]
+    fn test_insertion_order_does_not_change_hash() {
+        let first: String = sample(&[0, 1, 2, 3]).canonical_json().unwrap();
+        let second: String = sample(&[3, 2, 1, 0]).canonical_json().unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_unsorted_entries_serialize_canonically() {
+        let mut tree: Tree = sample(&[0, 1]);
+        tree.entries.reverse();
+        assert_eq!(
+            tree.canonical_json().unwrap(),
+            sample(&[1, 0]).canonical_json().unwrap()
+        );
     }
 }
@@ -0,0 +1,591 @@
+use crate::lockfile;
+use crate::objects::{ObjectType, encode_object};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const PACK_MAGIC: &[u8; 4] = b"NPCK";
+const INDEX_MAGIC: &[u8; 4] = b"NPIX";
+const PACK_VERSION: u32 = 1;
+const HASH_LEN: usize = 32;
+const FAN_OUT_LEN: usize = 256;
+
+const BLOCK: usize = 16;
+const MAX_INSERT: usize = 127;
+const COPY_OP: u8 = 0x80;
+
+const DELTA_WINDOW: usize = 10;
+const MAX_DELTA_DEPTH: usize = 10;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn type_code(kind: ObjectType) -> u8 {
+    match kind {
+        ObjectType::Blob => 1,
+        ObjectType::Tree => 2,
+        ObjectType::Commit => 3,
+        ObjectType::Tag => 4,
+    }
+}
+
+fn type_from_code(code: u8) -> io::Result<ObjectType> {
+    match code {
+        1 => Ok(ObjectType::Blob),
+        2 => Ok(ObjectType::Tree),
+        3 => Ok(ObjectType::Commit),
+        4 => Ok(ObjectType::Tag),
+        _ => Err(invalid("unknown object type in pack")),
+    }
+}
+
+fn hash_to_bytes(hash: &str) -> io::Result<[u8; HASH_LEN]> {
+    if hash.len() != HASH_LEN * 2 {
+        return Err(invalid("object hash has the wrong length"));
+    }
+    let mut bytes: [u8; HASH_LEN] = [0; HASH_LEN];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16)
+            .map_err(|_| invalid("object hash is not hex"))?;
+    }
+    Ok(bytes)
+}
+
+fn bytes_to_hash(bytes: &[u8]) -> String {
+    bytes.iter().map(|b: &u8| format!("{:02x}", b)).collect()
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value: usize = 0;
+    let mut shift: u32 = 0;
+    loop {
+        let byte: u8 = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+fn flush_insert(out: &mut Vec<u8>, pending: &mut Vec<u8>) {
+    for chunk in pending.chunks(MAX_INSERT) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+    pending.clear();
+}
+
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    write_varint(&mut out, base.len());
+    write_varint(&mut out, target.len());
+
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    for start in (0..base.len().saturating_sub(BLOCK - 1)).step_by(BLOCK) {
+        blocks.entry(&base[start..start + BLOCK]).or_insert(start);
+    }
+
+    let mut pending: Vec<u8> = Vec::new();
+    let mut i: usize = 0;
+    while i < target.len() {
+        if i + BLOCK <= target.len()
+            && let Some(&start) = blocks.get(&target[i..i + BLOCK])
+        {
+            let mut len: usize = BLOCK;
+            while start + len < base.len()
+                && i + len < target.len()
+                && base[start + len] == target[i + len]
+            {
+                len += 1;
+            }
+
+            flush_insert(&mut out, &mut pending);
+            out.push(COPY_OP);
+            write_varint(&mut out, start);
+            write_varint(&mut out, len);
+            i += len;
+            continue;
+        }
+
+        pending.push(target[i]);
+        i += 1;
+    }
+    flush_insert(&mut out, &mut pending);
+    out
+}
+
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut pos: usize = 0;
+    if read_varint(delta, &mut pos)? != base.len() {
+        return None;
+    }
+    let size: usize = read_varint(delta, &mut pos)?;
+
+    let mut out: Vec<u8> = Vec::with_capacity(size);
+    while pos < delta.len() {
+        let op: u8 = delta[pos];
+        pos += 1;
+        if op == COPY_OP {
+            let start: usize = read_varint(delta, &mut pos)?;
+            let len: usize = read_varint(delta, &mut pos)?;
+            out.extend_from_slice(base.get(start..start.checked_add(len)?)?);
+        } else if op > 0 && (op as usize) <= MAX_INSERT {
+            let len: usize = op as usize;
+            out.extend_from_slice(delta.get(pos..pos + len)?);
+            pos += len;
+        } else {
+            return None;
+        }
+    }
+
+    (out.len() == size).then_some(out)
+}
+
+pub struct PackObject {
+    pub hash: String,
+    pub kind: ObjectType,
+    pub body: Vec<u8>,
+    pub name_hint: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PackStats {
+    pub objects: usize,
+    pub deltas: usize,
+}
+
+fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+fn choose_deltas(objects: &[PackObject]) -> Vec<Option<(usize, Vec<u8>)>> {
+    let mut order: Vec<usize> = (0..objects.len()).collect();
+    order.sort_by(|&a: &usize, &b: &usize| {
+        let (a, b) = (&objects[a], &objects[b]);
+        type_code(a.kind)
+            .cmp(&type_code(b.kind))
+            .then_with(|| a.name_hint.cmp(&b.name_hint))
+            .then_with(|| b.body.len().cmp(&a.body.len()))
+    });
+
+    let mut deltas: Vec<Option<(usize, Vec<u8>)>> = (0..objects.len()).map(|_| None).collect();
+    let mut depth: Vec<usize> = vec![0; objects.len()];
+
+    for (position, &target) in order.iter().enumerate() {
+        let object: &PackObject = &objects[target];
+        if matches!(object.kind, ObjectType::Commit | ObjectType::Tag)
+            || object.body.len() < BLOCK * 2
+        {
+            continue;
+        }
+
+        let mut best: Option<(usize, Vec<u8>)> = None;
+        for &base in order[position.saturating_sub(DELTA_WINDOW)..position]
+            .iter()
+            .rev()
+        {
+            if objects[base].kind != object.kind || depth[base] >= MAX_DELTA_DEPTH {
+                continue;
+            }
+            let delta: Vec<u8> = create_delta(&objects[base].body, &object.body);
+            let limit: usize = best
+                .as_ref()
+                .map(|(_, current): &(usize, Vec<u8>)| current.len())
+                .unwrap_or(object.body.len() / 2);
+            if delta.len() < limit {
+                best = Some((base, delta));
+            }
+        }
+
+        if let Some((base, delta)) = best {
+            depth[target] = depth[base] + 1;
+            deltas[target] = Some((base, delta));
+        }
+    }
+    deltas
+}
+
+pub fn write_pack(pack_dir: &Path, objects: &[PackObject]) -> io::Result<(String, PackStats)> {
+    fs::create_dir_all(pack_dir)?;
+
+    let deltas: Vec<Option<(usize, Vec<u8>)>> = choose_deltas(objects);
+    let mut stats: PackStats = PackStats {
+        objects: objects.len(),
+        deltas: 0,
+    };
+
+    let mut pack: Vec<u8> = Vec::new();
+    pack.extend_from_slice(PACK_MAGIC);
+    pack.extend_from_slice(&PACK_VERSION.to_be_bytes());
+    pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+    let mut entries: Vec<([u8; HASH_LEN], u64)> = Vec::new();
+    for (object, delta) in objects.iter().zip(&deltas) {
+        entries.push((hash_to_bytes(&object.hash)?, pack.len() as u64));
+        pack.push(type_code(object.kind));
+
+        let data: &[u8] = match delta {
+            Some((base, delta)) => {
+                stats.deltas += 1;
+                pack.push(1);
+                pack.extend_from_slice(&(object.body.len() as u64).to_be_bytes());
+                pack.extend_from_slice(&hash_to_bytes(&objects[*base].hash)?);
+                delta
+            }
+            None => {
+                pack.push(0);
+                pack.extend_from_slice(&(object.body.len() as u64).to_be_bytes());
+                &object.body
+            }
+        };
+        let compressed: Vec<u8> = compress(data)?;
+        pack.extend_from_slice(&(compressed.len() as u64).to_be_bytes());
+        pack.extend_from_slice(&compressed);
+    }
+    let pack_checksum = Sha256::digest(&pack);
+    pack.extend_from_slice(&pack_checksum);
+
+    entries.sort();
+    let mut index: Vec<u8> = Vec::new();
+    index.extend_from_slice(INDEX_MAGIC);
+    index.extend_from_slice(&PACK_VERSION.to_be_bytes());
+    index.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for first in 0..FAN_OUT_LEN {
+        let count: usize = entries.partition_point(|(hash, _)| (hash[0] as usize) <= first);
+        index.extend_from_slice(&(count as u32).to_be_bytes());
+    }
+    for (hash, _) in &entries {
+        index.extend_from_slice(hash);
+    }
+    for (_, offset) in &entries {
+        index.extend_from_slice(&offset.to_be_bytes());
+    }
+    index.extend_from_slice(&pack_checksum);
+    let index_checksum = Sha256::digest(&index);
+    index.extend_from_slice(&index_checksum);
+
+    // Both files must be durable before the caller deletes the loose copies
+    // they replace; the pack goes first so an index never names a missing pack.
+    let name: String = format!("pack-{}", bytes_to_hash(&pack_checksum));
+    for (extension, data) in [("pack", &pack), ("idx", &index)] {
+        let temp_path: PathBuf = pack_dir.join(format!("tmp_{}.{}", name, extension));
+        let mut file: File = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, pack_dir.join(format!("{}.{}", name, extension)))?;
+        lockfile::sync_dir(pack_dir);
+    }
+    if let Some(objects_dir) = pack_dir.parent() {
+        lockfile::sync_dir(objects_dir);
+    }
+
+    Ok((name, stats))
+}
+
+pub struct Pack {
+    pub name: String,
+    pack_path: PathBuf,
+    pack_len: u64,
+    hashes: Vec<[u8; HASH_LEN]>,
+    offsets: Vec<u64>,
+    fan_out: Vec<u32>,
+}
+
+impl Pack {
+    pub fn open(pack_dir: &Path, name: &str) -> io::Result<Pack> {
+        let index: Vec<u8> = fs::read(pack_dir.join(format!("{}.idx", name)))?;
+        let header_len: usize = INDEX_MAGIC.len() + 8;
+        if index.len() < header_len + FAN_OUT_LEN * 4 + HASH_LEN * 2
+            || !index.starts_with(INDEX_MAGIC)
+        {
+            return Err(invalid("pack index is truncated"));
+        }
+        let (body, checksum) = index.split_at(index.len() - HASH_LEN);
+        if Sha256::digest(body)[..] != *checksum {
+            return Err(invalid("pack index checksum mismatch"));
+        }
+
+        // The index records the pack's trailer; a pack that was truncated or
+        // swapped underneath its index no longer ends with it.
+        let pack_path: PathBuf = pack_dir.join(format!("{}.pack", name));
+        let mut file: File = File::open(&pack_path)?;
+        let pack_len: u64 = file.metadata()?.len();
+        if pack_len < (PACK_MAGIC.len() + 8 + HASH_LEN) as u64 {
+            return Err(invalid("pack is truncated"));
+        }
+        let mut trailer: [u8; HASH_LEN] = [0; HASH_LEN];
+        file.seek(SeekFrom::End(-(HASH_LEN as i64)))?;
+        file.read_exact(&mut trailer)?;
+        if trailer[..] != body[body.len() - HASH_LEN..] {
+            return Err(invalid("pack checksum does not match its index"));
+        }
+
+        let read_u32 = |pos: usize| -> u32 {
+            u32::from_be_bytes(index[pos..pos + 4].try_into().unwrap_or_default())
+        };
+        if read_u32(INDEX_MAGIC.len()) != PACK_VERSION {
+            return Err(invalid("unsupported pack index version"));
+        }
+        let count: usize = read_u32(INDEX_MAGIC.len() + 4) as usize;
+        if body.len() != header_len + FAN_OUT_LEN * 4 + count * (HASH_LEN + 8) + HASH_LEN {
+            return Err(invalid("pack index has the wrong size"));
+        }
+
+        let fan_out: Vec<u32> = (0..FAN_OUT_LEN)
+            .map(|i: usize| read_u32(header_len + i * 4))
+            .collect();
+        let hashes_start: usize = header_len + FAN_OUT_LEN * 4;
+        let hashes: Vec<[u8; HASH_LEN]> = index[hashes_start..hashes_start + count * HASH_LEN]
+            .chunks(HASH_LEN)
+            .map(|chunk: &[u8]| chunk.try_into().unwrap_or([0; HASH_LEN]))
+            .collect();
+        let offsets_start: usize = hashes_start + count * HASH_LEN;
+        let offsets: Vec<u64> = index[offsets_start..offsets_start + count * 8]
+            .chunks(8)
+            .map(|chunk: &[u8]| u64::from_be_bytes(chunk.try_into().unwrap_or_default()))
+            .collect();
+
+        Ok(Pack {
+            name: name.to_string(),
+            pack_path,
+            pack_len,
+            hashes,
+            offsets,
+            fan_out,
+        })
+    }
+
+    /// Hashes the whole pack and compares it with its trailer. This reads
+    /// every byte, so it is left to `fsck` rather than done on open.
+    pub fn verify(&self) -> io::Result<()> {
+        let data: Vec<u8> = fs::read(&self.pack_path)?;
+        if data.len() < HASH_LEN {
+            return Err(invalid("pack is truncated"));
+        }
+        let (body, trailer) = data.split_at(data.len() - HASH_LEN);
+        if Sha256::digest(body)[..] != *trailer || !body.starts_with(PACK_MAGIC) {
+            return Err(invalid("pack checksum mismatch"));
+        }
+        Ok(())
+    }
+
+    fn position(&self, hash: &[u8; HASH_LEN]) -> Option<usize> {
+        let first: usize = hash[0] as usize;
+        let start: usize = if first == 0 {
+            0
+        } else {
+            self.fan_out[first - 1] as usize
+        };
+        let end: usize = self.fan_out[first] as usize;
+        self.hashes
+            .get(start..end)?
+            .binary_search(hash)
+            .ok()
+            .map(|i: usize| start + i)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        hash_to_bytes(hash)
+            .ok()
+            .and_then(|bytes: [u8; HASH_LEN]| self.position(&bytes))
+            .is_some()
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = String> + '_ {
+        self.hashes
+            .iter()
+            .map(|hash: &[u8; HASH_LEN]| bytes_to_hash(hash))
+    }
+
+    pub fn read(&self, hash: &str) -> io::Result<Option<Vec<u8>>> {
+        let Ok(bytes) = hash_to_bytes(hash) else {
+            return Ok(None);
+        };
+        if self.position(&bytes).is_none() {
+            return Ok(None);
+        }
+        let mut file: File = File::open(&self.pack_path)?;
+        let (kind, body) = self.read_entry(&mut file, &bytes, 0)?;
+        Ok(Some(encode_object(kind, &body)))
+    }
+
+    fn read_entry(
+        &self,
+        file: &mut File,
+        hash: &[u8; HASH_LEN],
+        depth: usize,
+    ) -> io::Result<(ObjectType, Vec<u8>)> {
+        if depth > MAX_DELTA_DEPTH {
+            return Err(invalid("delta chain is too deep"));
+        }
+        let position: usize = self
+            .position(hash)
+            .ok_or_else(|| invalid("delta base is missing from pack"))?;
+        file.seek(SeekFrom::Start(self.offsets[position]))?;
+
+        let mut header: [u8; 10] = [0; 10];
+        file.read_exact(&mut header)?;
+        let kind: ObjectType = type_from_code(header[0])?;
+        let is_delta: bool = header[1] == 1;
+        let size: usize = u64::from_be_bytes(header[2..10].try_into().unwrap_or_default()) as usize;
+
+        let mut base: [u8; HASH_LEN] = [0; HASH_LEN];
+        if is_delta {
+            file.read_exact(&mut base)?;
+        }
+        let mut length: [u8; 8] = [0; 8];
+        file.read_exact(&mut length)?;
+        let length: u64 = u64::from_be_bytes(length);
+        let remaining: u64 = self.pack_len.saturating_sub(file.stream_position()?);
+        if length > remaining {
+            return Err(invalid("packed object is truncated"));
+        }
+        let mut compressed: Vec<u8> = vec![0; length as usize];
+        file.read_exact(&mut compressed)?;
+
+        let mut data: Vec<u8> = Vec::new();
+        ZlibDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+
+        let body: Vec<u8> = if is_delta {
+            let (_, base_body) = self.read_entry(file, &base, depth + 1)?;
+            apply_delta(&base_body, &data).ok_or_else(|| invalid("delta does not apply"))?
+        } else {
+            data
+        };
+        if body.len() != size {
+            return Err(invalid("packed object has the wrong size"));
+        }
+        Ok((kind, body))
+    }
+}
+
+pub fn list_packs(pack_dir: &Path) -> io::Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    if !pack_dir.is_dir() {
+        return Ok(names);
+    }
+    for entry in fs::read_dir(pack_dir)? {
+        let file_name: String = entry?.file_name().to_string_lossy().to_string();
+        if let Some(name) = file_name.strip_suffix(".idx")
+            && name.starts_with("pack-")
+        {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::hash_object;
+    use tempfile::TempDir;
+
+    fn object(kind: ObjectType, body: Vec<u8>, name_hint: &str) -> PackObject {
+        PackObject {
+            hash: hash_object(kind, &body),
+            kind,
+            body,
+            name_hint: name_hint.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let base: Vec<u8> = (0..2000).map(|i: u32| (i % 251) as u8).collect();
+        let mut target: Vec<u8> = base.clone();
+        target.splice(500..510, b"edited".iter().copied());
+        target.extend_from_slice(b"appended tail");
+
+        let delta: Vec<u8> = create_delta(&base, &target);
+        assert!(delta.len() < 100);
+        assert_eq!(apply_delta(&base, &delta), Some(target));
+        assert_eq!(apply_delta(&base[1..], &delta), None);
+        assert_eq!(
+            apply_delta(b"", &create_delta(b"", b"new")),
+            Some(b"new".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_pack_roundtrip_with_deltas() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let text: String = (0..400).map(|i: u32| format!("line {}\n", i)).collect();
+
+        let mut objects: Vec<PackObject> = Vec::new();
+        for version in 0..5 {
+            let body: String = text.replace("line 200\n", &format!("changed {}\n", version));
+            objects.push(object(ObjectType::Blob, body.into_bytes(), "notes.txt"));
+        }
+        objects.push(object(ObjectType::Commit, b"{}".to_vec(), ""));
+
+        let (name, stats) = write_pack(temp.path(), &objects).unwrap();
+        assert_eq!(stats.objects, 6);
+        assert_eq!(stats.deltas, 4);
+        assert!(
+            fs::metadata(temp.path().join(format!("{}.pack", name)))
+                .unwrap()
+                .len()
+                < 2000
+        );
+
+        assert_eq!(list_packs(temp.path()).unwrap(), vec![name.clone()]);
+        let pack: Pack = Pack::open(temp.path(), &name).unwrap();
+        for object in &objects {
+            assert!(pack.contains(&object.hash));
+            assert_eq!(
+                pack.read(&object.hash).unwrap(),
+                Some(encode_object(object.kind, &object.body))
+            );
+        }
+        assert_eq!(
+            pack.read(&hash_object(ObjectType::Blob, b"missing"))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pack_rejects_corruption() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let objects: Vec<PackObject> = vec![object(ObjectType::Blob, b"hello".to_vec(), "a.txt")];
+        let (name, _) = write_pack(temp.path(), &objects).unwrap();
+        let pack_path: PathBuf = temp.path().join(format!("{}.pack", name));
+        let original: Vec<u8> = fs::read(&pack_path).unwrap();
+        Pack::open(temp.path(), &name).unwrap().verify().unwrap();
+
+        // An absurd compressed length must not be trusted for allocation.
+        let mut data: Vec<u8> = original.clone();
+        let length_at: usize = PACK_MAGIC.len() + 8 + 10;
+        data[length_at..length_at + 8].copy_from_slice(&(u64::MAX / 2).to_be_bytes());
+        fs::write(&pack_path, &data).unwrap();
+        let pack: Pack = Pack::open(temp.path(), &name).unwrap();
+        assert!(pack.read(&objects[0].hash).is_err());
+        assert!(pack.verify().is_err());
+
+        let mut data: Vec<u8> = original;
+        let last: usize = data.len() - 1;
+        data[last] ^= 0xff;
+        fs::write(&pack_path, &data).unwrap();
+        assert!(Pack::open(temp.path(), &name).is_err());
+    }
+}
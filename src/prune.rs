@@ -0,0 +1,128 @@
+use crate::error::NubError;
+use crate::object_store::ObjectStore;
+use crate::repository::Repository;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+pub const EXPIRE_CONFIG_KEY: &str = "gc.pruneExpire";
+pub const DEFAULT_EXPIRE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+pub fn parse_expiry(text: &str) -> Option<Duration> {
+    let text: &str = text.trim();
+    if text == "now" {
+        return Some(Duration::ZERO);
+    }
+    let split: usize = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit): (&str, &str) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    let seconds: u64 = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(number.checked_mul(seconds)?))
+}
+
+pub fn grace_period(repo: &Repository, expire: Option<&str>) -> Result<Duration> {
+    let configured: Option<String> = match expire {
+        Some(value) => Some(value.to_string()),
+        None => repo
+            .config()?
+            .get_str(EXPIRE_CONFIG_KEY)
+            .map(str::to_string),
+    };
+    match configured {
+        Some(value) => Ok(parse_expiry(&value).ok_or(NubError::InvalidExpiry(value))?),
+        None => Ok(DEFAULT_EXPIRE),
+    }
+}
+
+pub fn is_expired(modified: SystemTime, grace: Duration) -> bool {
+    modified.elapsed().is_ok_and(|age: Duration| age >= grace)
+}
+
+pub fn candidates(repo: &Repository, grace: Duration) -> Result<Vec<String>> {
+    let reachable: BTreeMap<String, String> = repo.reachable_objects()?;
+    let objects: &ObjectStore = repo.objects();
+
+    let mut expired: Vec<String> = Vec::new();
+    for hash in objects.loose_hashes()? {
+        if reachable.contains_key(&hash) {
+            continue;
+        }
+        if is_expired(objects.loose_modified(&hash)?, grace) {
+            expired.push(hash);
+        }
+    }
+    Ok(expired)
+}
+
+pub fn prune(repo: &Repository, grace: Duration) -> Result<Vec<String>> {
+    let expired: Vec<String> = candidates(repo, grace)?;
+    for hash in &expired {
+        repo.objects().remove_loose(hash)?;
+    }
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_expiry() {
+        assert_eq!(parse_expiry("now"), Some(Duration::ZERO));
+        assert_eq!(parse_expiry("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_expiry("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_expiry("2h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_expiry("2w"), Some(DEFAULT_EXPIRE));
+        assert_eq!(parse_expiry("14d"), Some(DEFAULT_EXPIRE));
+        assert_eq!(parse_expiry("soon"), None);
+        assert_eq!(parse_expiry("3y"), None);
+    }
+
+    #[test]
+    fn test_prunes_only_old_unreachable_objects() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("a.txt".to_string(), repo.write_blob(b"a").unwrap());
+        let tree: String = repo.write_tree_from_files(&files).unwrap();
+
+        let old: String = repo.write_blob(b"old").unwrap();
+        let fresh: String = repo.write_blob(b"fresh").unwrap();
+        let past: SystemTime = SystemTime::now() - Duration::from_secs(3600);
+        for hash in [&old, &tree] {
+            fs::File::options()
+                .append(true)
+                .open(repo.objects().path(hash))
+                .unwrap()
+                .set_modified(past)
+                .unwrap();
+        }
+
+        let mut expired: Vec<String> = candidates(&repo, Duration::from_secs(60)).unwrap();
+        expired.sort();
+        let mut expected: Vec<String> = vec![old.clone(), tree.clone()];
+        expected.sort();
+        assert_eq!(expired, expected);
+
+        repo.write_blob(b"old").unwrap();
+        assert_eq!(
+            candidates(&repo, Duration::from_secs(60)).unwrap(),
+            vec![tree.clone()]
+        );
+
+        assert_eq!(prune(&repo, Duration::ZERO).unwrap().len(), 4);
+        assert!(!repo.objects().contains(&fresh));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::lockfile;
+use crate::objects::commit::Author;
+use anyhow::Result;
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+pub const NULL_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub name: String,
+    pub email: String,
+    pub timestamp: DateTime<FixedOffset>,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    pub fn new(old: Option<&str>, new: &str, identity: &Author, message: &str) -> Self {
+        ReflogEntry {
+            old: old.unwrap_or(NULL_HASH).to_string(),
+            new: new.to_string(),
+            name: identity.name.clone(),
+            email: identity.email.clone(),
+            timestamp: Local::now().fixed_offset(),
+            message: message.lines().next().unwrap_or("").to_string(),
+        }
+    }
+
+    pub fn old_hash(&self) -> Option<&str> {
+        Some(self.old.as_str()).filter(|hash: &&str| *hash != NULL_HASH)
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "{} {} {} <{}> {} {}\t{}\n",
+            self.old,
+            self.new,
+            self.name,
+            self.email,
+            self.timestamp.timestamp(),
+            self.timestamp.format("%z"),
+            self.message
+        )
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+        let (old, rest) = header.split_once(' ')?;
+        let (new, rest) = rest.split_once(' ')?;
+        let (name, rest) = rest.split_once(" <")?;
+        let (email, rest) = rest.split_once("> ")?;
+        let (seconds, zone) = rest.trim().split_once(' ')?;
+        let sign: i32 = if zone.starts_with('-') { -1 } else { 1 };
+        let digits: i32 = zone.get(1..)?.parse().ok()?;
+        let offset: FixedOffset =
+            FixedOffset::east_opt(sign * ((digits / 100) * 3600 + (digits % 100) * 60))?;
+        let timestamp: DateTime<FixedOffset> =
+            offset.timestamp_opt(seconds.parse().ok()?, 0).single()?;
+        Some(ReflogEntry {
+            old: old.to_string(),
+            new: new.to_string(),
+            name: name.to_string(),
+            email: email.to_string(),
+            timestamp,
+            message: message.to_string(),
+        })
+    }
+}
+
+pub fn append(path: &Path, entry: &ReflogEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file: fs::File = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(entry.format().as_bytes())?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Vec<ReflogEntry>> {
+    let Ok(data) = fs::read_to_string(path) else {
+        return Ok(Vec::new());
+    };
+    Ok(data.lines().filter_map(ReflogEntry::parse).collect())
+}
+
+pub fn write(path: &Path, entries: &[ReflogEntry]) -> Result<()> {
+    let data: String = entries.iter().map(ReflogEntry::format).collect();
+    lockfile::write_atomic(path, data.as_bytes())
+}
+
+pub fn parse_date(spec: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let spec: String = spec.trim().to_ascii_lowercase().replace('.', " ");
+    match spec.as_str() {
+        "now" => return Some(now),
+        "yesterday" => return Some(now - Duration::days(1)),
+        _ => {}
+    }
+
+    let words: Vec<&str> = spec.split_whitespace().collect();
+    if let [count, unit, "ago"] = words.as_slice() {
+        let count: i64 = count.parse().ok()?;
+        let unit: &str = unit.trim_end_matches('s');
+        let span: Duration = match unit {
+            "second" => Duration::seconds(count),
+            "minute" => Duration::minutes(count),
+            "hour" => Duration::hours(count),
+            "day" => Duration::days(count),
+            "week" => Duration::weeks(count),
+            "month" => Duration::days(count * 30),
+            "year" => Duration::days(count * 365),
+            _ => return None,
+        };
+        return Some(now - span);
+    }
+
+    let text: &str = spec.trim();
+    let naive: NaiveDateTime = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })?;
+    Some(
+        Local
+            .from_local_datetime(&naive)
+            .earliest()?
+            .with_timezone(&Utc),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_entries_roundtrip() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let path: std::path::PathBuf = temp.path().join("logs/refs/heads/main");
+        let identity: Author = Author {
+            name: "Ada Lovelace".to_string(),
+            email: "ada@example.com".to_string(),
+        };
+
+        append(
+            &path,
+            &ReflogEntry::new(None, "aaaa", &identity, "commit (initial): first"),
+        )
+        .unwrap();
+        append(
+            &path,
+            &ReflogEntry::new(Some("aaaa"), "bbbb", &identity, "commit: second\nbody"),
+        )
+        .unwrap();
+
+        let entries: Vec<ReflogEntry> = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].old_hash(), None);
+        assert_eq!(entries[1].old_hash(), Some("aaaa"));
+        assert_eq!(entries[1].name, "Ada Lovelace");
+        assert_eq!(entries[1].message, "commit: second");
+
+        write(&path, &entries[1..]).unwrap();
+        assert_eq!(read(&path).unwrap(), entries[1..].to_vec());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let now: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+        assert_eq!(parse_date("yesterday", now), Some(now - Duration::days(1)));
+        assert_eq!(
+            parse_date("2.hours.ago", now),
+            Some(now - Duration::hours(2))
+        );
+        assert_eq!(
+            parse_date("3 weeks ago", now),
+            Some(now - Duration::weeks(3))
+        );
+        assert!(parse_date("2024-01-01", now).is_some());
+        assert_eq!(parse_date("someday", now), None);
+    }
+}
@@ -1,20 +1,109 @@
+use crate::config::Config;
 use crate::error::NubError;
+use crate::ignore::IgnoreRules;
+use crate::index::Index;
+use crate::lockfile::{self, LockFile};
+use crate::object_store::ObjectStore;
+use crate::objects::commit::Author;
+use crate::objects::tree::{EntryType, TreeChange, TreeEntry};
+use crate::objects::{
+    Blob, Commit, Object, ObjectType, Tag, Tree, decode_object, encode_object, hash_object,
+};
+use crate::reflog::{self, ReflogEntry};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const NUB_DIR: &str = ".nub-vcs";
 const OBJECTS_DIR: &str = "objects";
-const COMMITS_DIR: &str = "commits";
+const LEGACY_COMMITS_DIR: &str = "commits";
 const REFS_DIR: &str = "refs";
 const HEADS_DIR: &str = "heads";
+const TAGS_DIR: &str = "tags";
+const LOGS_DIR: &str = "logs";
 const HEAD_FILE: &str = "HEAD";
 const INDEX_FILE: &str = "index";
 const CONFIG_FILE: &str = "config";
+const MERGE_HEAD_FILE: &str = "MERGE_HEAD";
+const MERGE_MSG_FILE: &str = "MERGE_MSG";
+const INFO_DIR: &str = "info";
+const EXCLUDE_FILE: &str = "exclude";
+
+pub enum Head {
+    Branch(String),
+    Detached(String),
+}
+
+pub fn is_valid_ref_name(name: &str) -> bool {
+    !(name.is_empty()
+        || name == HEAD_FILE
+        || name.starts_with('-')
+        || name.ends_with('/')
+        || name.ends_with(".lock")
+        || name.contains("..")
+        || name.contains("//")
+        || name
+            .chars()
+            .any(|c: char| c.is_whitespace() || c.is_control() || "~^:?*[\\@{".contains(c))
+        || name.split('/').any(|part: &str| part.starts_with('.')))
+}
+
+/// Writes nested tree objects for a flat `path -> blob` map into `objects`
+/// and returns the root tree hash.
+pub fn write_tree_from_files(
+    objects: &ObjectStore,
+    files: &HashMap<String, String>,
+) -> Result<String> {
+    let files: BTreeMap<&str, &str> = files
+        .iter()
+        .map(|(path, hash)| (path.as_str(), hash.as_str()))
+        .collect();
+    write_tree_level(objects, &files)
+}
+
+fn write_tree_level(objects: &ObjectStore, files: &BTreeMap<&str, &str>) -> Result<String> {
+    let mut blobs: BTreeMap<&str, &str> = BTreeMap::new();
+    let mut dirs: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+
+    for (&path, &hash) in files {
+        match path.split_once('/') {
+            Some((dir, rest)) => {
+                dirs.entry(dir).or_default().insert(rest, hash);
+            }
+            None => {
+                blobs.insert(path, hash);
+            }
+        }
+    }
+
+    let mut tree: Tree = Tree::new();
+    for (name, hash) in blobs {
+        tree.add_entry(name.to_string(), hash.to_string(), EntryType::Blob);
+    }
+    for (name, children) in &dirs {
+        let subtree_hash: String = write_tree_level(objects, children)?;
+        tree.add_entry(name.to_string(), subtree_hash, EntryType::Tree);
+    }
+
+    let body: Vec<u8> = Object::Tree(tree).encode().map_err(NubError::from)?;
+    write_raw_object(objects, ObjectType::Tree, &body)
+}
+
+fn write_raw_object(objects: &ObjectStore, kind: ObjectType, body: &[u8]) -> Result<String> {
+    let hash: String = hash_object(kind, body);
+    // Always go through the loose store: an existing loose copy gets its
+    // mtime refreshed and a packed-only object gets a fresh loose copy, so
+    // prune never treats a just-written object as unreachable garbage.
+    objects.write(&hash, &encode_object(kind, body))?;
+    Ok(hash)
+}
 
 pub struct Repository {
     pub root: PathBuf,
     pub nub_dir: PathBuf,
+    objects: ObjectStore,
 }
 
 impl Repository {
@@ -27,23 +116,33 @@ impl Repository {
 
         fs::create_dir(&nub_dir)?;
         fs::create_dir(nub_dir.join(OBJECTS_DIR))?;
-        fs::create_dir(nub_dir.join(COMMITS_DIR))?;
         fs::create_dir(nub_dir.join(REFS_DIR))?;
         fs::create_dir(nub_dir.join(REFS_DIR).join(HEADS_DIR))?;
+        fs::create_dir(nub_dir.join(INFO_DIR))?;
 
         let repo: Repository = Repository {
             root: path.to_path_buf(),
+            objects: ObjectStore::new(nub_dir.join(OBJECTS_DIR)),
             nub_dir,
         };
 
         repo.init_head()?;
         repo.init_index()?;
         repo.init_config()?;
+        repo.init_exclude()?;
 
         Ok(repo)
     }
 
     pub fn find() -> Result<Self> {
+        let repo: Repository = Repository::find_unchecked()?;
+        if repo.legacy_commits_dir().exists() {
+            return Err(NubError::LegacyLayout.into());
+        }
+        Ok(repo)
+    }
+
+    pub fn find_unchecked() -> Result<Self> {
         let mut current: PathBuf = std::env::current_dir()?;
 
         loop {
@@ -51,6 +150,7 @@ impl Repository {
             if nub_dir.exists() && nub_dir.is_dir() {
                 return Ok(Repository {
                     root: current,
+                    objects: ObjectStore::new(nub_dir.join(OBJECTS_DIR)),
                     nub_dir,
                 });
             }
@@ -68,20 +168,20 @@ impl Repository {
     }
 
     fn init_index(&self) -> Result<()> {
-        let index_path: PathBuf = self.nub_dir.join(INDEX_FILE);
-        fs::write(index_path, "[]")?;
-        Ok(())
+        Index::default().save(&self.nub_dir.join(INDEX_FILE))
     }
 
     fn init_config(&self) -> Result<()> {
         let config_path: PathBuf = self.nub_dir.join(CONFIG_FILE);
-        let default_config: serde_json::Value = serde_json::json!({
-            "user": {
-                "name": "NUB User",
-                "email": "user@nub.local"
-            }
-        });
-        fs::write(config_path, serde_json::to_string_pretty(&default_config)?)?;
+        fs::write(config_path, "{}\n")?;
+        Ok(())
+    }
+
+    fn init_exclude(&self) -> Result<()> {
+        fs::write(
+            self.exclude_path(),
+            "# Patterns listed here are ignored in this repository only.\n",
+        )?;
         Ok(())
     }
 
@@ -89,8 +189,16 @@ impl Repository {
         self.nub_dir.join(OBJECTS_DIR)
     }
 
-    pub fn commits_dir(&self) -> PathBuf {
-        self.nub_dir.join(COMMITS_DIR)
+    pub fn legacy_commits_dir(&self) -> PathBuf {
+        self.nub_dir.join(LEGACY_COMMITS_DIR)
+    }
+
+    pub fn objects(&self) -> &ObjectStore {
+        &self.objects
+    }
+
+    pub fn objects_mut(&mut self) -> &mut ObjectStore {
+        &mut self.objects
     }
 
     pub fn refs_dir(&self) -> PathBuf {
@@ -101,6 +209,10 @@ impl Repository {
         self.refs_dir().join(HEADS_DIR)
     }
 
+    pub fn tags_dir(&self) -> PathBuf {
+        self.refs_dir().join(TAGS_DIR)
+    }
+
     pub fn head_path(&self) -> PathBuf {
         self.nub_dir.join(HEAD_FILE)
     }
@@ -112,11 +224,863 @@ impl Repository {
     pub fn config_path(&self) -> PathBuf {
         self.nub_dir.join(CONFIG_FILE)
     }
+
+    pub fn config(&self) -> Result<Config> {
+        Config::load(Some(&self.config_path()))
+    }
+
+    pub fn merge_head_path(&self) -> PathBuf {
+        self.nub_dir.join(MERGE_HEAD_FILE)
+    }
+
+    pub fn merge_msg_path(&self) -> PathBuf {
+        self.nub_dir.join(MERGE_MSG_FILE)
+    }
+
+    pub fn exclude_path(&self) -> PathBuf {
+        self.nub_dir.join(INFO_DIR).join(EXCLUDE_FILE)
+    }
+
+    pub fn ignore_rules(&self) -> IgnoreRules {
+        IgnoreRules::new(&self.root, &self.exclude_path())
+    }
+
+    pub fn read_head(&self) -> Result<Head> {
+        if !self.head_path().is_file() {
+            return Err(NubError::InvalidRepository.into());
+        }
+        let head: String = fs::read_to_string(self.head_path())?;
+        let head: &str = head.trim();
+        match head.strip_prefix("ref: ") {
+            Some(head_ref) => Ok(Head::Branch(
+                head_ref.trim_start_matches("refs/heads/").to_string(),
+            )),
+            None => Ok(Head::Detached(head.to_string())),
+        }
+    }
+
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        match self.read_head()? {
+            Head::Branch(name) => Ok(Some(name)),
+            Head::Detached(_) => Ok(None),
+        }
+    }
+
+    pub fn set_head_branch(&self, name: &str, reason: &str) -> Result<()> {
+        let old: Option<String> = self.head_commit_hash()?;
+        lockfile::write_atomic(
+            &self.head_path(),
+            format!("ref: refs/heads/{}", name).as_bytes(),
+        )?;
+        if let Some(new) = self.read_branch(name)? {
+            self.append_reflog(HEAD_FILE, old.as_deref(), &new, reason)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_head_detached(&self, hash: &str, reason: &str) -> Result<()> {
+        let old: Option<String> = self.head_commit_hash()?;
+        lockfile::write_atomic(&self.head_path(), hash.as_bytes())?;
+        self.append_reflog(HEAD_FILE, old.as_deref(), hash, reason)
+    }
+
+    pub fn update_ref(
+        &self,
+        name: &str,
+        new: &str,
+        expected: Option<&str>,
+        reason: &str,
+    ) -> Result<()> {
+        let path: PathBuf = self.nub_dir.join(name);
+        let mut lock: LockFile = LockFile::acquire(&path)?;
+        let current: Option<String> = match fs::read_to_string(&path) {
+            Ok(value) => Some(value.trim().to_string()).filter(|v: &String| !v.is_empty()),
+            Err(_) => None,
+        };
+        if current.as_deref() != expected {
+            return Err(NubError::RefChanged(name.to_string()).into());
+        }
+        lock.write(new.as_bytes())?;
+        lock.commit()?;
+        self.log_ref_update(name, current.as_deref(), new, reason)
+    }
+
+    pub fn reflog_path(&self, refname: &str) -> PathBuf {
+        self.nub_dir.join(LOGS_DIR).join(refname)
+    }
+
+    pub fn read_reflog(&self, refname: &str) -> Result<Vec<ReflogEntry>> {
+        reflog::read(&self.reflog_path(refname))
+    }
+
+    pub fn write_reflog(&self, refname: &str, entries: &[ReflogEntry]) -> Result<()> {
+        reflog::write(&self.reflog_path(refname), entries)
+    }
+
+    pub fn list_reflogs(&self) -> Result<Vec<String>> {
+        let logs_dir: PathBuf = self.nub_dir.join(LOGS_DIR);
+        let mut refs: Vec<String> = walkdir::WalkDir::new(&logs_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let rel: &Path = e.path().strip_prefix(&logs_dir).ok()?;
+                Some(rel.to_string_lossy().replace("\\", "/"))
+            })
+            .filter(|name: &String| !name.ends_with(".lock"))
+            .collect();
+        refs.sort();
+        Ok(refs)
+    }
+
+    pub fn move_reflog(&self, from: &str, to: &str) -> Result<()> {
+        let from: PathBuf = self.reflog_path(from);
+        if from.is_file() {
+            let to: PathBuf = self.reflog_path(to);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(from, to)?;
+        }
+        Ok(())
+    }
+
+    fn append_reflog(
+        &self,
+        refname: &str,
+        old: Option<&str>,
+        new: &str,
+        reason: &str,
+    ) -> Result<()> {
+        let identity: Author = self.config()?.identity();
+        let entry: ReflogEntry = ReflogEntry::new(old, new, &identity, reason);
+        reflog::append(&self.reflog_path(refname), &entry)
+    }
+
+    fn log_ref_update(
+        &self,
+        refname: &str,
+        old: Option<&str>,
+        new: &str,
+        reason: &str,
+    ) -> Result<()> {
+        self.append_reflog(refname, old, new, reason)?;
+        if refname != HEAD_FILE
+            && let Some(branch) = self.current_branch()?
+            && refname == format!("refs/heads/{}", branch)
+        {
+            self.append_reflog(HEAD_FILE, old, new, reason)?;
+        }
+        Ok(())
+    }
+
+    pub fn branch_path(&self, name: &str) -> PathBuf {
+        self.heads_dir().join(name)
+    }
+
+    pub fn read_branch(&self, name: &str) -> Result<Option<String>> {
+        let branch_path: PathBuf = self.branch_path(name);
+        if !branch_path.is_file() {
+            return Ok(None);
+        }
+        let hash: String = fs::read_to_string(branch_path)?.trim().to_string();
+        Ok(if hash.is_empty() { None } else { Some(hash) })
+    }
+
+    pub fn write_branch(&self, name: &str, hash: &str, reason: &str) -> Result<()> {
+        let old: Option<String> = self.read_branch(name)?;
+        lockfile::write_atomic(&self.branch_path(name), hash.as_bytes())?;
+        self.log_ref_update(
+            &format!("refs/heads/{}", name),
+            old.as_deref(),
+            hash,
+            reason,
+        )
+    }
+
+    pub fn list_branches(&self) -> Result<Vec<String>> {
+        let mut branches: Vec<String> = walkdir::WalkDir::new(self.heads_dir())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let rel: &Path = e.path().strip_prefix(self.heads_dir()).ok()?;
+                Some(rel.to_string_lossy().replace("\\", "/"))
+            })
+            .collect();
+        branches.sort();
+        Ok(branches)
+    }
+
+    pub fn list_refs(&self) -> Result<Vec<(String, String)>> {
+        let mut refs: Vec<(String, String)> = Vec::new();
+        for entry in walkdir::WalkDir::new(self.refs_dir())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let Ok(rel) = entry.path().strip_prefix(&self.nub_dir) else {
+                continue;
+            };
+            let hash: String = fs::read_to_string(entry.path())?.trim().to_string();
+            if !hash.is_empty() {
+                refs.push((rel.to_string_lossy().replace("\\", "/"), hash));
+            }
+        }
+        refs.sort();
+        Ok(refs)
+    }
+
+    pub fn delete_branch(&self, name: &str) -> Result<()> {
+        let branch_path: PathBuf = self.branch_path(name);
+        if !branch_path.is_file() {
+            return Err(NubError::BranchNotFound(name.to_string()).into());
+        }
+        let lock: LockFile = LockFile::acquire(&branch_path)?;
+        fs::remove_file(&branch_path)?;
+        drop(lock);
+        let log_path: PathBuf = self.reflog_path(&format!("refs/heads/{}", name));
+        if log_path.is_file() {
+            fs::remove_file(log_path)?;
+        }
+
+        let heads_dir: PathBuf = self.heads_dir();
+        let mut dir: Option<&Path> = branch_path.parent();
+        while let Some(current) = dir {
+            if current == heads_dir || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+        Ok(())
+    }
+
+    pub fn tag_path(&self, name: &str) -> PathBuf {
+        self.tags_dir().join(name)
+    }
+
+    pub fn read_ref(&self, refname: &str) -> Result<Option<String>> {
+        let ref_path: PathBuf = self.nub_dir.join(refname);
+        if !ref_path.is_file() {
+            return Ok(None);
+        }
+        let hash: String = fs::read_to_string(ref_path)?.trim().to_string();
+        Ok(if hash.is_empty() { None } else { Some(hash) })
+    }
+
+    /// Removes the `n`th newest entry from a ref's reflog while holding the
+    /// ref lock, pointing the ref at the new newest entry or deleting it once
+    /// the log is empty. Fails with `RefChanged` if the ref no longer matches
+    /// the top of its log.
+    pub fn drop_reflog_entry(&self, refname: &str, n: usize) -> Result<Option<ReflogEntry>> {
+        let ref_path: PathBuf = self.nub_dir.join(refname);
+        let mut lock: LockFile = LockFile::acquire(&ref_path)?;
+        let mut entries: Vec<ReflogEntry> = self.read_reflog(refname)?;
+        if n >= entries.len() {
+            return Ok(None);
+        }
+        let current: Option<String> = self.read_ref(refname)?;
+        if current.as_deref() != entries.last().map(|top: &ReflogEntry| top.new.as_str()) {
+            return Err(NubError::RefChanged(refname.to_string()).into());
+        }
+
+        let position: usize = entries.len() - 1 - n;
+        let entry: ReflogEntry = entries.remove(position);
+        if let Some(next) = entries.get_mut(position) {
+            next.old = entry.old.clone();
+        }
+
+        match entries.last() {
+            Some(top) => {
+                lock.write(top.new.as_bytes())?;
+                self.write_reflog(refname, &entries)?;
+                lock.commit()?;
+            }
+            None => {
+                fs::remove_file(&ref_path)?;
+                drop(lock);
+                fs::remove_file(self.reflog_path(refname))?;
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    pub fn read_tag_ref(&self, name: &str) -> Result<Option<String>> {
+        let tag_path: PathBuf = self.tag_path(name);
+        if !tag_path.is_file() {
+            return Ok(None);
+        }
+        let hash: String = fs::read_to_string(tag_path)?.trim().to_string();
+        Ok(if hash.is_empty() { None } else { Some(hash) })
+    }
+
+    pub fn write_tag_ref(&self, name: &str, hash: &str) -> Result<()> {
+        lockfile::write_atomic(&self.tag_path(name), hash.as_bytes())
+    }
+
+    pub fn list_tags(&self) -> Result<Vec<String>> {
+        let mut tags: Vec<String> = walkdir::WalkDir::new(self.tags_dir())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let rel: &Path = e.path().strip_prefix(self.tags_dir()).ok()?;
+                Some(rel.to_string_lossy().replace("\\", "/"))
+            })
+            .collect();
+        tags.sort();
+        Ok(tags)
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<()> {
+        let tag_path: PathBuf = self.tag_path(name);
+        if !tag_path.is_file() {
+            return Err(NubError::TagNotFound(name.to_string()).into());
+        }
+        let lock: LockFile = LockFile::acquire(&tag_path)?;
+        fs::remove_file(&tag_path)?;
+        drop(lock);
+
+        let tags_dir: PathBuf = self.tags_dir();
+        let mut dir: Option<&Path> = tag_path.parent();
+        while let Some(current) = dir {
+            if current == tags_dir || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+        Ok(())
+    }
+
+    pub fn ancestors(&self, hash: &str) -> Result<HashSet<String>> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut queue: VecDeque<String> = VecDeque::from([hash.to_string()]);
+        while let Some(current) = queue.pop_front() {
+            if !seen.insert(current.clone()) {
+                continue;
+            }
+            queue.extend(self.read_commit(&current)?.parents);
+        }
+        Ok(seen)
+    }
+
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        Ok(self.ancestors(descendant)?.contains(ancestor))
+    }
+
+    /// Finds the best common ancestor of two commits. A criss-cross history
+    /// can leave several equally good bases; the one furthest from the root
+    /// (highest generation) wins, then the newest, then the smallest hash, so
+    /// the same pair of commits always merges against the same base.
+    pub fn merge_base(&self, ours: &str, theirs: &str) -> Result<Option<String>> {
+        let our_ancestors: HashSet<String> = self.ancestors(ours)?;
+        let mut commits: HashMap<String, Commit> = HashMap::new();
+        for hash in self.ancestors(theirs)? {
+            if our_ancestors.contains(&hash) {
+                let commit: Commit = self.read_commit(&hash)?;
+                commits.insert(hash, commit);
+            }
+        }
+
+        // Every ancestor of a common commit is common too, so the walks
+        // below never leave `commits`.
+        let mut redundant: HashSet<&String> = HashSet::new();
+        let mut queue: VecDeque<&String> = commits
+            .values()
+            .flat_map(|commit: &Commit| commit.parents.iter())
+            .collect();
+        while let Some(current) = queue.pop_front() {
+            if redundant.insert(current) {
+                queue.extend(commits[current].parents.iter());
+            }
+        }
+        let bases: Vec<&String> = commits
+            .keys()
+            .filter(|hash: &&String| !redundant.contains(hash))
+            .collect();
+
+        let mut generations: HashMap<&String, usize> = HashMap::new();
+        let mut stack: Vec<&String> = bases.clone();
+        while let Some(&hash) = stack.last() {
+            if generations.contains_key(hash) {
+                stack.pop();
+                continue;
+            }
+            let parents: &Vec<String> = &commits[hash].parents;
+            let pending: Vec<&String> = parents
+                .iter()
+                .filter(|parent: &&String| !generations.contains_key(parent))
+                .collect();
+            if pending.is_empty() {
+                let generation: usize = parents
+                    .iter()
+                    .map(|parent: &String| generations[parent] + 1)
+                    .max()
+                    .unwrap_or(0);
+                generations.insert(hash, generation);
+                stack.pop();
+            } else {
+                stack.extend(pending);
+            }
+        }
+
+        Ok(bases
+            .into_iter()
+            .max_by(|a: &&String, b: &&String| {
+                (generations[a], commits[*a].timestamp)
+                    .cmp(&(generations[b], commits[*b].timestamp))
+                    .then_with(|| b.cmp(a))
+            })
+            .cloned())
+    }
+
+    pub fn root_commits(&self) -> Result<Vec<String>> {
+        let mut roots: Vec<String> = self
+            .list_refs()?
+            .into_iter()
+            .map(|(_, hash)| hash)
+            .collect();
+        roots.extend(self.head_commit_hash()?);
+        for refname in self.list_reflogs()? {
+            for entry in self.read_reflog(&refname)? {
+                roots.extend(
+                    [entry.old_hash(), Some(entry.new.as_str())]
+                        .into_iter()
+                        .flatten()
+                        .filter(|hash: &&str| self.objects().contains(hash))
+                        .map(str::to_string),
+                );
+            }
+        }
+        if let Ok(merge_head) = fs::read_to_string(self.merge_head_path()) {
+            roots.push(merge_head.trim().to_string());
+        }
+        roots.sort();
+        roots.dedup();
+        Ok(roots)
+    }
+
+    pub fn reachable_objects(&self) -> Result<BTreeMap<String, String>> {
+        let mut reachable: BTreeMap<String, String> = BTreeMap::new();
+        let mut commits: Vec<String> = Vec::new();
+        for root in self.root_commits()? {
+            let mut hash: String = root;
+            let mut kind: ObjectType = self.read_object(&hash)?.0;
+            while kind == ObjectType::Tag {
+                let tag: Tag = self.read_tag(&hash)?;
+                reachable.insert(hash, String::new());
+                kind = ObjectType::parse(&tag.object_type)
+                    .ok_or_else(|| NubError::CorruptObject(tag.object.clone()))?;
+                hash = tag.object;
+            }
+            match kind {
+                ObjectType::Tree => self.mark_tree(&hash, "", &mut reachable)?,
+                ObjectType::Blob => {
+                    reachable.entry(hash).or_default();
+                }
+                _ => commits.push(hash),
+            }
+        }
+        while let Some(hash) = commits.pop() {
+            if reachable.contains_key(&hash) {
+                continue;
+            }
+            reachable.insert(hash.clone(), String::new());
+            let commit: Commit = self.read_commit(&hash)?;
+            self.mark_tree(&commit.tree, "", &mut reachable)?;
+            commits.extend(commit.parents);
+        }
+
+        for (path, hash) in self.read_index()?.entries() {
+            if let Some(hash) = hash {
+                reachable
+                    .entry(hash.clone())
+                    .or_insert_with(|| path.clone());
+            }
+        }
+        Ok(reachable)
+    }
+
+    fn mark_tree(
+        &self,
+        tree_hash: &str,
+        path: &str,
+        reachable: &mut BTreeMap<String, String>,
+    ) -> Result<()> {
+        if reachable.contains_key(tree_hash) {
+            return Ok(());
+        }
+        reachable.insert(tree_hash.to_string(), path.to_string());
+
+        for entry in self.read_tree(tree_hash)?.entries {
+            let entry_path: String = if path.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", path, entry.name)
+            };
+            match entry.entry_type {
+                EntryType::Blob => {
+                    reachable.entry(entry.hash).or_insert(entry_path);
+                }
+                EntryType::Tree => self.mark_tree(&entry.hash, &entry_path, reachable)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn head_commit_hash(&self) -> Result<Option<String>> {
+        match self.read_head()? {
+            Head::Branch(name) => self.read_branch(&name),
+            Head::Detached(hash) => Ok(Some(hash)),
+        }
+    }
+
+    pub fn update_head(&self, hash: &str, expected: Option<&str>, reason: &str) -> Result<()> {
+        match self.read_head()? {
+            Head::Branch(name) => {
+                self.update_ref(&format!("refs/heads/{}", name), hash, expected, reason)
+            }
+            Head::Detached(_) => self.update_ref(HEAD_FILE, hash, expected, reason),
+        }
+    }
+
+    pub fn head_commit(&self) -> Result<Option<Commit>> {
+        match self.head_commit_hash()? {
+            Some(hash) => Ok(Some(self.read_commit(&hash)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn resolve_ref(&self, name: &str) -> Result<Option<String>> {
+        if name == HEAD_FILE {
+            return self.head_commit_hash();
+        }
+        if let Some(branch) = name
+            .strip_prefix("refs/heads/")
+            .or_else(|| name.strip_prefix("heads/"))
+        {
+            return self.read_branch(branch);
+        }
+        if let Some(tag) = name
+            .strip_prefix("refs/tags/")
+            .or_else(|| name.strip_prefix("tags/"))
+        {
+            return self.read_tag_ref(tag);
+        }
+        if !is_valid_ref_name(name) {
+            return Ok(None);
+        }
+        if name.starts_with("refs/") {
+            return self.read_ref(name);
+        }
+        if let Some(hash) = self.read_branch(name)? {
+            return Ok(Some(hash));
+        }
+        if let Some(hash) = self.read_tag_ref(name)? {
+            return Ok(Some(hash));
+        }
+        self.read_ref(&format!("refs/{}", name))
+    }
+
+    pub fn peel_to_commit(&self, hash: &str) -> Result<String> {
+        let mut hash: String = hash.to_string();
+        loop {
+            match self.read_object(&hash)?.0 {
+                ObjectType::Commit => return Ok(hash),
+                ObjectType::Tag => hash = self.read_tag(&hash)?.object,
+                _ => return Err(NubError::UnexpectedObjectType(hash, "commit".to_string()).into()),
+            }
+        }
+    }
+
+    pub fn reflog_ref_name(&self, name: &str) -> Result<String> {
+        if name.is_empty() {
+            return Ok(match self.current_branch()? {
+                Some(branch) => format!("refs/heads/{}", branch),
+                None => HEAD_FILE.to_string(),
+            });
+        }
+        if name == HEAD_FILE || name.starts_with("refs/") {
+            return Ok(name.to_string());
+        }
+        if let Some(branch) = name.strip_prefix("heads/") {
+            return Ok(format!("refs/heads/{}", branch));
+        }
+        let candidates: [String; 2] = [format!("refs/heads/{}", name), format!("refs/{}", name)];
+        Ok(candidates
+            .iter()
+            .find(|refname: &&String| self.reflog_path(refname).is_file())
+            .unwrap_or(&candidates[0])
+            .clone())
+    }
+
+    fn resolve_reflog(&self, rev: &str, name: &str, spec: &str) -> Result<String> {
+        let refname: String = self.reflog_ref_name(name)?;
+        let entries: Vec<ReflogEntry> = self.read_reflog(&refname)?;
+        let not_found = || NubError::RevisionNotFound(rev.to_string());
+
+        let hash: Option<String> = if let Ok(n) = spec.parse::<usize>() {
+            if n < entries.len() {
+                Some(entries[entries.len() - 1 - n].new.clone())
+            } else if n == entries.len() {
+                entries
+                    .first()
+                    .and_then(|entry: &ReflogEntry| entry.old_hash())
+                    .map(str::to_string)
+            } else {
+                None
+            }
+        } else {
+            let cutoff: DateTime<Utc> =
+                reflog::parse_date(spec, Utc::now()).ok_or_else(not_found)?;
+            entries
+                .iter()
+                .rev()
+                .find(|entry: &&ReflogEntry| entry.timestamp <= cutoff)
+                .map(|entry: &ReflogEntry| entry.new.clone())
+        };
+        Ok(hash.ok_or_else(not_found)?)
+    }
+
+    pub fn resolve_commit(&self, rev: &str) -> Result<String> {
+        if let Some((name, spec)) = rev.split_once("@{")
+            && let Some(spec) = spec.strip_suffix('}')
+        {
+            let hash: String = self.resolve_reflog(rev, name, spec)?;
+            return self.peel_to_commit(&hash);
+        }
+
+        if let Some(hash) = self.resolve_ref(rev)? {
+            return self.peel_to_commit(&hash);
+        }
+
+        if rev.len() >= 4 && rev.chars().all(|c: char| c.is_ascii_hexdigit()) {
+            let mut matches: Vec<String> = Vec::new();
+            for hash in self.find_objects(rev)? {
+                if self.read_object(&hash)?.0 == ObjectType::Commit {
+                    matches.push(hash);
+                }
+            }
+            match matches.len() {
+                0 => {}
+                1 => return Ok(matches.remove(0)),
+                _ => return Err(NubError::AmbiguousRevision(rev.to_string()).into()),
+            }
+        }
+
+        Err(NubError::RevisionNotFound(rev.to_string()).into())
+    }
+
+    pub fn resolve_object(&self, name: &str) -> Result<String> {
+        if let Some(hash) = self.resolve_ref(name)? {
+            return Ok(hash);
+        }
+
+        if name.len() >= 4 && name.chars().all(|c: char| c.is_ascii_hexdigit()) {
+            let mut matches: Vec<String> = self.find_objects(name)?;
+            match matches.len() {
+                0 => {}
+                1 => return Ok(matches.remove(0)),
+                _ => return Err(NubError::AmbiguousRevision(name.to_string()).into()),
+            }
+        }
+
+        Err(NubError::ObjectNotFound(name.to_string()).into())
+    }
+
+    fn find_objects(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self.objects().find_prefix(prefix)?)
+    }
+
+    pub fn read_object(&self, hash: &str) -> Result<(ObjectType, Vec<u8>)> {
+        let data: Vec<u8> = self
+            .objects()
+            .read(hash)
+            .map_err(|_| NubError::CorruptObject(hash.to_string()))?
+            .ok_or_else(|| NubError::ObjectNotFound(hash.to_string()))?;
+        let (kind, body) =
+            decode_object(&data).ok_or_else(|| NubError::CorruptObject(hash.to_string()))?;
+        Ok((kind, body.to_vec()))
+    }
+
+    fn read_object_as(&self, hash: &str, expected: ObjectType) -> Result<Vec<u8>> {
+        let (kind, body) = self.read_object(hash)?;
+        if kind != expected {
+            return Err(
+                NubError::UnexpectedObjectType(hash.to_string(), expected.to_string()).into(),
+            );
+        }
+        Ok(body)
+    }
+
+    pub fn read_commit(&self, hash: &str) -> Result<Commit> {
+        let body: Vec<u8> = self.read_object_as(hash, ObjectType::Commit)?;
+        let commit: Commit =
+            serde_json::from_slice(&body).map_err(|_| NubError::CorruptObject(hash.to_string()))?;
+        Ok(commit)
+    }
+
+    pub fn read_tree(&self, hash: &str) -> Result<Tree> {
+        let body: Vec<u8> = self.read_object_as(hash, ObjectType::Tree)?;
+        let tree: Tree =
+            serde_json::from_slice(&body).map_err(|_| NubError::CorruptObject(hash.to_string()))?;
+        Ok(tree)
+    }
+
+    pub fn read_tag(&self, hash: &str) -> Result<Tag> {
+        let body: Vec<u8> = self.read_object_as(hash, ObjectType::Tag)?;
+        let tag: Tag =
+            serde_json::from_slice(&body).map_err(|_| NubError::CorruptObject(hash.to_string()))?;
+        Ok(tag)
+    }
+
+    pub fn read_blob(&self, hash: &str) -> Result<Vec<u8>> {
+        self.read_object_as(hash, ObjectType::Blob)
+    }
+
+    pub fn write_object(&self, object: &Object) -> Result<String> {
+        let body: Vec<u8> = object.encode().map_err(NubError::from)?;
+        self.write_raw_object(object.kind(), &body)
+    }
+
+    pub fn write_raw_object(&self, kind: ObjectType, body: &[u8]) -> Result<String> {
+        write_raw_object(&self.objects, kind, body)
+    }
+
+    pub fn write_blob(&self, content: &[u8]) -> Result<String> {
+        self.write_object(&Object::Blob(Blob::new(content.to_vec())))
+    }
+
+    pub fn write_tree_from_files(&self, files: &HashMap<String, String>) -> Result<String> {
+        write_tree_from_files(&self.objects, files)
+    }
+
+    pub fn tree_files(&self, tree_hash: &str) -> Result<HashMap<String, String>> {
+        let mut files: HashMap<String, String> = HashMap::new();
+        self.collect_tree_files(tree_hash, "", &mut files)?;
+        Ok(files)
+    }
+
+    fn collect_tree_files(
+        &self,
+        tree_hash: &str,
+        prefix: &str,
+        files: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        for entry in self.read_tree(tree_hash)?.entries {
+            let path: String = format!("{}{}", prefix, entry.name.replace("\\", "/"));
+            match entry.entry_type {
+                EntryType::Blob => {
+                    files.insert(path, entry.hash);
+                }
+                EntryType::Tree => {
+                    self.collect_tree_files(&entry.hash, &format!("{}/", path), files)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn commit_files(&self, commit_hash: &str) -> Result<HashMap<String, String>> {
+        let commit: Commit = self.read_commit(commit_hash)?;
+        self.tree_files(&commit.tree)
+    }
+
+    pub fn diff_trees(
+        &self,
+        old_tree: Option<&str>,
+        new_tree: Option<&str>,
+    ) -> Result<Vec<TreeChange>> {
+        let mut changes: Vec<TreeChange> = Vec::new();
+        self.diff_tree_level(old_tree, new_tree, "", &mut changes)?;
+        Ok(changes)
+    }
+
+    fn diff_tree_level(
+        &self,
+        old_tree: Option<&str>,
+        new_tree: Option<&str>,
+        prefix: &str,
+        changes: &mut Vec<TreeChange>,
+    ) -> Result<()> {
+        if old_tree == new_tree {
+            return Ok(());
+        }
+
+        let old_entries: BTreeMap<String, TreeEntry> = self.tree_entries(old_tree)?;
+        let new_entries: BTreeMap<String, TreeEntry> = self.tree_entries(new_tree)?;
+        let names: BTreeSet<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+
+        for name in names {
+            let path: String = format!("{}{}", prefix, name);
+            let old: Option<&TreeEntry> = old_entries.get(name);
+            let new: Option<&TreeEntry> = new_entries.get(name);
+
+            let old_blob: Option<String> = old
+                .filter(|e| e.entry_type == EntryType::Blob)
+                .map(|e| e.hash.clone());
+            let new_blob: Option<String> = new
+                .filter(|e| e.entry_type == EntryType::Blob)
+                .map(|e| e.hash.clone());
+            if old_blob != new_blob {
+                changes.push(TreeChange {
+                    path: path.clone(),
+                    old: old_blob,
+                    new: new_blob,
+                });
+            }
+
+            let old_subtree: Option<&str> = old
+                .filter(|e| e.entry_type == EntryType::Tree)
+                .map(|e| e.hash.as_str());
+            let new_subtree: Option<&str> = new
+                .filter(|e| e.entry_type == EntryType::Tree)
+                .map(|e| e.hash.as_str());
+            self.diff_tree_level(old_subtree, new_subtree, &format!("{}/", path), changes)?;
+        }
+        Ok(())
+    }
+
+    fn tree_entries(&self, tree_hash: Option<&str>) -> Result<BTreeMap<String, TreeEntry>> {
+        let Some(tree_hash) = tree_hash else {
+            return Ok(BTreeMap::new());
+        };
+        Ok(self
+            .read_tree(tree_hash)?
+            .entries
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect())
+    }
+
+    pub fn head_files(&self) -> Result<HashMap<String, String>> {
+        match self.head_commit()? {
+            Some(commit) => self.tree_files(&commit.tree),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    pub fn read_index(&self) -> Result<Index> {
+        Index::load(&self.index_path())
+    }
+
+    pub fn lock_index(&self) -> Result<LockFile> {
+        LockFile::acquire(&self.index_path())
+    }
+
+    pub fn write_index(&self, index: &Index) -> Result<()> {
+        index.save(&self.index_path())
+    }
+
+    pub fn write_locked_index(&self, index: &Index, lock: LockFile) -> Result<()> {
+        index.write_to(lock)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pack::PackObject;
     use tempfile::TempDir;
 
     #[test]
@@ -126,7 +1090,7 @@ mod tests {
 
         assert!(repo.nub_dir.exists());
         assert!(repo.objects_dir().exists());
-        assert!(repo.commits_dir().exists());
+        assert!(!repo.legacy_commits_dir().exists());
         assert!(repo.heads_dir().exists());
         assert!(repo.head_path().exists());
         assert!(repo.index_path().exists());
@@ -148,4 +1112,298 @@ mod tests {
         let head_content: String = fs::read_to_string(repo.head_path()).unwrap();
         assert_eq!(head_content, "ref: refs/heads/main");
     }
+
+    #[test]
+    fn test_nested_trees_share_unchanged_subtrees() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("README.md".to_string(), repo.write_blob(b"readme").unwrap());
+        files.insert("src/main.rs".to_string(), repo.write_blob(b"main").unwrap());
+        files.insert(
+            "src/cmd/add.rs".to_string(),
+            repo.write_blob(b"add").unwrap(),
+        );
+        let first: String = repo.write_tree_from_files(&files).unwrap();
+
+        let root: Tree = repo.read_tree(&first).unwrap();
+        assert_eq!(root.entries.len(), 2);
+        assert!(root.entries.iter().all(|e| !e.name.contains('/')));
+        assert_eq!(repo.tree_files(&first).unwrap(), files);
+
+        files.insert(
+            "README.md".to_string(),
+            repo.write_blob(b"changed").unwrap(),
+        );
+        let second: String = repo.write_tree_from_files(&files).unwrap();
+        let src_hash = |tree: &str| {
+            repo.read_tree(tree)
+                .unwrap()
+                .entries
+                .into_iter()
+                .find(|e| e.name == "src")
+                .unwrap()
+                .hash
+        };
+        assert_eq!(src_hash(&first), src_hash(&second));
+
+        let changes: Vec<TreeChange> = repo.diff_trees(Some(&first), Some(&second)).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "README.md");
+    }
+
+    #[test]
+    fn test_snapshot_hashing_is_deterministic() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+
+        let paths: Vec<String> = (0..50)
+            .map(|i| format!("dir{}/file{}.txt", i % 7, i))
+            .collect();
+        let forward: HashMap<String, String> = paths
+            .iter()
+            .map(|p| (p.clone(), repo.write_blob(p.as_bytes()).unwrap()))
+            .collect();
+        let mut backward: HashMap<String, String> = HashMap::new();
+        for path in paths.iter().rev() {
+            backward.insert(path.clone(), forward[path].clone());
+        }
+
+        assert_eq!(
+            repo.write_tree_from_files(&forward).unwrap(),
+            repo.write_tree_from_files(&backward).unwrap()
+        );
+
+        let mut forward_index: Index = Index::default();
+        for (path, hash) in &forward {
+            forward_index.stage(path.clone(), hash.clone());
+        }
+        let mut backward_index: Index = Index::default();
+        for (path, hash) in &backward {
+            backward_index.stage(path.clone(), hash.clone());
+        }
+
+        repo.write_index(&forward_index).unwrap();
+        let first: Vec<u8> = fs::read(repo.index_path()).unwrap();
+        repo.write_index(&backward_index).unwrap();
+        let second: Vec<u8> = fs::read(repo.index_path()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(repo.read_index().unwrap(), forward_index);
+    }
+
+    #[test]
+    fn test_rewriting_packed_object_writes_loose_copy() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let mut repo: Repository = Repository::init(temp.path()).unwrap();
+        let hash: String = hash_object(ObjectType::Blob, b"packed");
+        repo.objects_mut()
+            .write_pack(&[PackObject {
+                hash: hash.clone(),
+                kind: ObjectType::Blob,
+                body: b"packed".to_vec(),
+                name_hint: "packed.txt".to_string(),
+            }])
+            .unwrap();
+        assert!(repo.objects().contains(&hash));
+        assert!(!repo.objects().contains_loose(&hash));
+
+        assert_eq!(repo.write_blob(b"packed").unwrap(), hash);
+        assert!(repo.objects().contains_loose(&hash));
+    }
+
+    #[test]
+    fn test_typed_reads_report_corruption() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+
+        let mut files: HashMap<String, String> = HashMap::new();
+        files.insert("a.txt".to_string(), repo.write_blob(b"a").unwrap());
+        let tree_hash: String = repo.write_tree_from_files(&files).unwrap();
+        let commit: Commit = Commit::new(
+            tree_hash.clone(),
+            Vec::new(),
+            Author {
+                name: "Test".to_string(),
+                email: "test@nub.local".to_string(),
+            },
+            "first".to_string(),
+        );
+        let commit_hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+        repo.update_head(&commit_hash, None, "commit").unwrap();
+
+        assert_eq!(
+            repo.resolve_ref("refs/heads/main").unwrap(),
+            Some(commit_hash.clone())
+        );
+        assert_eq!(repo.head_commit().unwrap().unwrap().tree, tree_hash);
+        assert_eq!(repo.head_files().unwrap(), files);
+
+        fs::write(repo.objects().path(&tree_hash), "not a tree").unwrap();
+        let err: anyhow::Error = repo.head_files().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::CorruptObject(hash)) if hash == &tree_hash
+        ));
+    }
+
+    #[test]
+    fn test_tags_resolve_to_commits() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let tree_hash: String = repo.write_tree_from_files(&HashMap::new()).unwrap();
+        let commit: Commit = Commit::new(tree_hash, Vec::new(), author, "first".to_string());
+        let commit_hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+
+        repo.write_tag_ref("light", &commit_hash).unwrap();
+        let tag: Tag = Tag::new(
+            commit_hash.clone(),
+            "commit".to_string(),
+            "v1.0".to_string(),
+            Author {
+                name: "Test".to_string(),
+                email: "test@nub.local".to_string(),
+            },
+            "release".to_string(),
+        );
+        let tag_hash: String = repo.write_object(&Object::Tag(tag)).unwrap();
+        repo.write_tag_ref("v1.0", &tag_hash).unwrap();
+
+        assert_eq!(repo.list_tags().unwrap(), vec!["light", "v1.0"]);
+        assert_eq!(repo.resolve_commit("light").unwrap(), commit_hash);
+        assert_eq!(repo.resolve_commit("tags/v1.0").unwrap(), commit_hash);
+        assert_eq!(repo.resolve_object("refs/tags/v1.0").unwrap(), tag_hash);
+        assert!(repo.reachable_objects().unwrap().contains_key(&tag_hash));
+
+        repo.delete_tag("v1.0").unwrap();
+        assert!(repo.resolve_commit("v1.0").is_err());
+        assert!(!repo.tags_dir().join("v1.0").exists());
+    }
+
+    #[test]
+    fn test_ref_updates_compare_and_swap() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+
+        repo.update_head("aaaa", None, "test").unwrap();
+        assert!(repo.update_head("bbbb", None, "test").is_err());
+        repo.update_head("bbbb", Some("aaaa"), "test").unwrap();
+
+        let err: anyhow::Error = repo
+            .update_ref("refs/heads/main", "cccc", Some("aaaa"), "test")
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NubError>(),
+            Some(NubError::RefChanged(name)) if name == "refs/heads/main"
+        ));
+        assert_eq!(repo.read_branch("main").unwrap(), Some("bbbb".to_string()));
+
+        let lock: LockFile = repo.lock_index().unwrap();
+        assert!(repo.write_index(&Index::default()).is_err());
+        repo.write_locked_index(&Index::default(), lock).unwrap();
+        assert!(!repo.nub_dir.join("index.lock").exists());
+    }
+
+    #[test]
+    fn test_reflog_records_updates_and_resolves() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let tree_hash: String = repo.write_tree_from_files(&HashMap::new()).unwrap();
+        let mut hashes: Vec<String> = Vec::new();
+        for message in ["first", "second", "third"] {
+            let author: Author = Author {
+                name: "Test".to_string(),
+                email: "test@nub.local".to_string(),
+            };
+            let parents: Vec<String> = hashes.last().cloned().into_iter().collect();
+            let commit: Commit =
+                Commit::new(tree_hash.clone(), parents, author, message.to_string());
+            let hash: String = repo.write_object(&Object::Commit(commit)).unwrap();
+            repo.update_head(&hash, hashes.last().map(String::as_str), message)
+                .unwrap();
+            hashes.push(hash);
+        }
+
+        let entries: Vec<ReflogEntry> = repo.read_reflog("refs/heads/main").unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(repo.read_reflog(HEAD_FILE).unwrap(), entries);
+        assert_eq!(repo.resolve_commit("main@{0}").unwrap(), hashes[2]);
+        assert_eq!(repo.resolve_commit("@{1}").unwrap(), hashes[1]);
+        assert_eq!(repo.resolve_commit("HEAD@{2}").unwrap(), hashes[0]);
+        assert!(repo.resolve_commit("main@{3}").is_err());
+        assert_eq!(repo.resolve_commit("main@{now}").unwrap(), hashes[2]);
+
+        repo.set_head_branch("other", "checkout: moving from main to other")
+            .unwrap();
+        repo.write_branch("other", &hashes[0], "branch: Created from HEAD")
+            .unwrap();
+        assert_eq!(repo.resolve_commit("other@{0}").unwrap(), hashes[0]);
+        assert_eq!(repo.resolve_commit("HEAD@{0}").unwrap(), hashes[0]);
+        assert!(repo.root_commits().unwrap().contains(&hashes[1]));
+    }
+
+    fn write_test_commit(repo: &Repository, parents: Vec<String>, message: &str) -> String {
+        let tree_hash: String = repo.write_tree_from_files(&HashMap::new()).unwrap();
+        let author: Author = Author {
+            name: "Test".to_string(),
+            email: "test@nub.local".to_string(),
+        };
+        let commit: Commit = Commit::new(tree_hash, parents, author, message.to_string());
+        repo.write_object(&Object::Commit(commit)).unwrap()
+    }
+
+    #[test]
+    fn test_merge_base_on_long_linear_history() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let mut history: Vec<String> = vec![write_test_commit(&repo, Vec::new(), "0")];
+        for n in 1..400 {
+            let parent: String = history[n - 1].clone();
+            history.push(write_test_commit(&repo, vec![parent], &n.to_string()));
+        }
+        let topic: String = write_test_commit(&repo, vec![history[250].clone()], "topic");
+
+        assert_eq!(
+            repo.merge_base(&history[399], &topic).unwrap(),
+            Some(history[250].clone())
+        );
+        assert_eq!(
+            repo.merge_base(&history[399], &history[100]).unwrap(),
+            Some(history[100].clone())
+        );
+    }
+
+    #[test]
+    fn test_merge_base_on_criss_cross_merge() {
+        let temp: TempDir = TempDir::new().unwrap();
+        let repo: Repository = Repository::init(temp.path()).unwrap();
+        let root: String = write_test_commit(&repo, Vec::new(), "root");
+        let left: String = write_test_commit(&repo, vec![root.clone()], "left");
+        let right: String = write_test_commit(&repo, vec![root.clone()], "right");
+        let ours: String = write_test_commit(&repo, vec![left.clone(), right.clone()], "ours");
+        let theirs: String = write_test_commit(&repo, vec![right.clone(), left.clone()], "theirs");
+
+        let base: String = repo.merge_base(&ours, &theirs).unwrap().unwrap();
+        assert!(base == left || base == right);
+        assert_ne!(base, root);
+        assert_eq!(repo.merge_base(&theirs, &ours).unwrap(), Some(base));
+
+        // The deeper of two criss-cross bases is chosen, whichever side it is on.
+        let deeper: String = write_test_commit(&repo, vec![left.clone()], "deeper");
+        let ours: String = write_test_commit(&repo, vec![deeper.clone(), right.clone()], "ours");
+        let theirs: String =
+            write_test_commit(&repo, vec![right.clone(), deeper.clone()], "theirs");
+        assert_eq!(
+            repo.merge_base(&ours, &theirs).unwrap(),
+            Some(deeper.clone())
+        );
+        assert_eq!(repo.merge_base(&theirs, &ours).unwrap(), Some(deeper));
+
+        let unrelated: String = write_test_commit(&repo, Vec::new(), "unrelated");
+        assert_eq!(repo.merge_base(&ours, &unrelated).unwrap(), None);
+    }
 }
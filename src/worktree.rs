@@ -0,0 +1,110 @@
+use crate::error::NubError;
+use crate::ignore;
+use crate::index::{FileStat, Index};
+use crate::objects::Blob;
+use crate::repository::Repository;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::path::{Component, Path, PathBuf};
+
+pub fn hash_file(repo: &Repository, path: &str) -> Option<String> {
+    let full_path: PathBuf = repo.root.join(path);
+    if !full_path.is_file() {
+        return None;
+    }
+    fs::read(full_path)
+        .ok()
+        .map(|data: Vec<u8>| Blob::new(data).hash)
+}
+
+pub fn hash_file_cached(repo: &Repository, index: &mut Index, path: &str) -> Option<String> {
+    let full_path: PathBuf = repo.root.join(path);
+    let metadata: Metadata = fs::metadata(&full_path)
+        .ok()
+        .filter(|metadata: &Metadata| metadata.is_file())?;
+    let stat: FileStat = FileStat::from_metadata(&metadata);
+    if let Some(hash) = index.cached_hash(path, &stat) {
+        return Some(hash.clone());
+    }
+
+    let hash: String = Blob::new(fs::read(&full_path).ok()?).hash;
+    index.record_stat(path.to_string(), hash.clone(), stat);
+    Some(hash)
+}
+
+pub fn write_file(repo: &Repository, path: &str, content: &[u8]) -> Result<()> {
+    let full_path: PathBuf = repo.root.join(path);
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if full_path.is_dir() {
+        fs::remove_dir_all(&full_path)?;
+    }
+    fs::write(full_path, content)?;
+    Ok(())
+}
+
+pub fn remove_file(repo: &Repository, path: &str) -> Result<()> {
+    let full_path: PathBuf = repo.root.join(path);
+    if full_path.is_file() {
+        fs::remove_file(&full_path)?;
+    }
+
+    let mut dir: Option<&Path> = full_path.parent();
+    while let Some(current) = dir {
+        if current == repo.root || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+    Ok(())
+}
+
+pub fn switch_files(
+    repo: &Repository,
+    from: &HashMap<String, String>,
+    to: &HashMap<String, String>,
+) -> Result<()> {
+    for (path, hash) in to {
+        if from.get(path) != Some(hash) {
+            let content: Vec<u8> = repo.read_blob(hash)?;
+            write_file(repo, path, &content)?;
+        }
+    }
+    for path in from.keys() {
+        if !to.contains_key(path) {
+            remove_file(repo, path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn repo_relative(repo: &Repository, arg: &str) -> Result<String> {
+    let mut absolute: PathBuf = PathBuf::new();
+    for component in std::env::current_dir()?.join(arg).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            other => absolute.push(other),
+        }
+    }
+
+    let relative: &Path = absolute
+        .strip_prefix(&repo.root)
+        .map_err(|_| NubError::PathOutsideRepository(arg.to_string()))?;
+    Ok(relative.to_string_lossy().replace("\\", "/"))
+}
+
+pub fn matches_pathspec(path: &str, spec: &str) -> bool {
+    if spec.is_empty() || path == spec || path.starts_with(&format!("{}/", spec)) {
+        return true;
+    }
+    spec.contains(['*', '?', '['])
+        && ignore::glob_match(
+            &spec.chars().collect::<Vec<char>>(),
+            &path.chars().collect::<Vec<char>>(),
+        )
+}
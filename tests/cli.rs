@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn nub(dir: &Path, home: &Path, args: &[&str]) -> Output {
+    let mut command: Command = Command::new(env!("CARGO_BIN_EXE_nub-vcs"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", home)
+        .env("NUB_SYSTEM_CONFIG", home.join("nubconfig"))
+        .env("NUB_GLOBAL_CONFIG", home.join(".nubconfig"));
+    for (key, _) in env::vars() {
+        if key.starts_with("NUB_CONFIG_") {
+            command.env_remove(key);
+        }
+    }
+    command.output().unwrap()
+}
+
+#[test]
+fn test_fresh_repository_commits_without_configured_identity() {
+    let home: TempDir = TempDir::new().unwrap();
+    let work: TempDir = TempDir::new().unwrap();
+    fs::write(work.path().join("hello.txt"), "hello\n").unwrap();
+
+    for args in [&["init"][..], &["add", "."], &["commit", "-m", "x"]] {
+        let output: Output = nub(work.path(), home.path(), args);
+        assert!(
+            output.status.success(),
+            "nub {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let log: Output = nub(work.path(), home.path(), &["log"]);
+    assert!(log.status.success());
+    assert!(String::from_utf8_lossy(&log.stdout).contains("Author: NUB User <user@nub.local>"));
+}